@@ -0,0 +1,229 @@
+//! Decoder and parser throughput over `event plain all` traffic.
+//!
+//! `data/event_plain_all.jsonl` is a session in the [`eslrs::record`] format:
+//! one call's channel events plus a heartbeat and a background job, with
+//! hostnames, addresses and UUIDs replaced by placeholders. The received
+//! frames are repeated [`CALLS`] times in setup to get a realistic volume.
+//!
+//! Parsing is compared against the eager `HashMap` parse plain events used
+//! before headers were indexed lazily, see [`eager`].
+//!
+//! Run with `cargo bench --features record`.
+use bytes::{Bytes, BytesMut};
+use criterion::{BatchSize, Criterion, Throughput, criterion_group, criterion_main};
+use eslrs::codec::ESLCodec;
+use eslrs::event::{Event, PlainEvent};
+use eslrs::record::{Direction, Replay};
+use std::hint::black_box;
+
+const SESSION: &[u8] = include_bytes!("data/event_plain_all.jsonl");
+const CALLS: usize = 40;
+
+/// Bytes received from FreeSWITCH over [`CALLS`] replays of the session.
+fn traffic() -> Vec<u8> {
+    let replay = Replay::from_reader(SESSION).unwrap();
+    let session: Vec<u8> = replay
+        .remaining()
+        .filter(|r| r.direction == Direction::In)
+        .flat_map(|r| r.data.iter().copied())
+        .collect();
+    session.repeat(CALLS)
+}
+
+fn decode_all(traffic: &[u8]) -> Vec<Event> {
+    let mut src = BytesMut::from(traffic);
+    let mut codec = ESLCodec::new();
+    let mut events = vec![];
+    while let Some(event) = codec.decode(&mut src).unwrap() {
+        events.push(event);
+    }
+    events
+}
+
+/// The plain event parse as it was before lazy header indexing: every
+/// header copied into a `HashMap` up front, looked up by exact name.
+mod eager {
+    use bytes::Bytes;
+    use std::collections::HashMap;
+
+    pub struct PlainEvent(HashMap<Bytes, Vec<Bytes>>, Option<Bytes>);
+
+    impl PlainEvent {
+        pub fn parse(data: &Bytes) -> Self {
+            let mut map: HashMap<Bytes, Vec<Bytes>> = HashMap::new();
+
+            let mut last: usize = 0;
+            while data.len() > last
+                && let Some(n) = data[last..].iter().position(|c| *c == b'\n')
+            {
+                let line_end = last + n;
+                let line = data.slice(last..line_end);
+                if line.is_empty() {
+                    last = line_end + 1;
+                    break;
+                };
+                if let Some(i) = line.iter().position(|c| *c == b':') {
+                    let k = data.slice_ref(line[..i].trim_ascii());
+                    let v = data.slice_ref(line[i + 1..].trim_ascii());
+                    map.entry(k).or_default().push(v);
+                }
+                if data.len() > line_end + 1 {
+                    last = line_end + 1;
+                } else {
+                    break;
+                }
+            }
+            let body = data.clone().split_off(last);
+            PlainEvent(map, if body.is_empty() { None } else { Some(body) })
+        }
+
+        pub fn get_header(&self, k: &str) -> Option<&str> {
+            let b = Bytes::copy_from_slice(k.as_ref());
+            self.0
+                .get(&b)
+                .and_then(|v| v.first())
+                .map(|b| str::from_utf8(b).unwrap_or("INVALID UTF8"))
+        }
+
+        pub fn get_body(&self) -> Option<&Bytes> {
+            self.1.as_ref()
+        }
+
+        pub fn headers(&self) -> impl Iterator<Item = (&[u8], &Bytes)> {
+            self.0
+                .iter()
+                .flat_map(|(k, v)| v.iter().map(|v| (k.as_ref(), v)))
+        }
+    }
+}
+
+fn bench_decoder(c: &mut Criterion) {
+    let traffic = traffic();
+    let mut group = c.benchmark_group("decoder");
+    group.throughput(Throughput::Bytes(traffic.len() as u64));
+
+    group.bench_function("frames", |b| {
+        b.iter_batched(
+            || BytesMut::from(traffic.as_slice()),
+            |mut src| {
+                let mut codec = ESLCodec::new();
+                while let Some(event) = codec.decode(&mut src).unwrap() {
+                    black_box(event);
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("frames_content_type", |b| {
+        b.iter_batched(
+            || BytesMut::from(traffic.as_slice()),
+            |mut src| {
+                let mut codec = ESLCodec::new();
+                while let Some(event) = codec.decode(&mut src).unwrap() {
+                    black_box(event.get_content_type());
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn bench_parser(c: &mut Criterion) {
+    let events: Vec<Event> = decode_all(&traffic())
+        .into_iter()
+        .filter(Event::is_plain_event)
+        .collect();
+    let bodies: Vec<Bytes> = events
+        .iter()
+        .map(|e| e.get_body().unwrap().clone())
+        .collect();
+    let bytes: usize = bodies.iter().map(Bytes::len).sum();
+
+    // The eager parse only matches exact names, so look headers up as sent
+    let lookup = ["Event-Name", "Unique-ID", "variable_sip_call_id"];
+
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(bytes as u64));
+    group.bench_function("lazy", |b| {
+        b.iter_batched(
+            || events.clone(),
+            |events| {
+                for e in events {
+                    black_box(PlainEvent::try_from(e).unwrap());
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("eager", |b| {
+        b.iter(|| {
+            for body in &bodies {
+                black_box(eager::PlainEvent::parse(body));
+            }
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("parse_lookup");
+    group.throughput(Throughput::Bytes(bytes as u64));
+    group.bench_function("lazy", |b| {
+        b.iter_batched(
+            || events.clone(),
+            |events| {
+                for e in events {
+                    let plain = PlainEvent::try_from(e).unwrap();
+                    for name in lookup {
+                        black_box(plain.get_header(name));
+                    }
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("eager", |b| {
+        b.iter(|| {
+            for body in &bodies {
+                let plain = eager::PlainEvent::parse(body);
+                for name in lookup {
+                    black_box(plain.get_header(name));
+                }
+            }
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("parse_iterate");
+    group.throughput(Throughput::Bytes(bytes as u64));
+    group.bench_function("lazy", |b| {
+        b.iter_batched(
+            || events.clone(),
+            |events| {
+                for e in events {
+                    let plain = PlainEvent::try_from(e).unwrap();
+                    for header in plain.headers() {
+                        black_box(header);
+                    }
+                    black_box(plain.get_body());
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("eager", |b| {
+        b.iter(|| {
+            for body in &bodies {
+                let plain = eager::PlainEvent::parse(body);
+                for header in plain.headers() {
+                    black_box(header);
+                }
+                black_box(plain.get_body());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_decoder, bench_parser);
+criterion_main!(benches);
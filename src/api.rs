@@ -4,17 +4,72 @@ use std::{
     ops::{Deref, DerefMut},
     time::Duration,
 };
-use tokio::{
-    io::{AsyncRead, AsyncWrite},
-    net::{TcpStream, ToSocketAddrs},
-    time::{error::Elapsed, timeout},
-};
+#[cfg(feature = "tokio")]
+use tokio::net::{TcpStream, ToSocketAddrs};
 
-use crate::{ESLConnection, ESLError, event::Reply};
+use crate::{
+    Connector, ESLConnection, ESLError, ESLUrl, EventSubscription, UrlError,
+    codec::CodecLimits,
+    event::Reply,
+    rt::{Driver, Runtime, Transport},
+};
+#[cfg(feature = "tokio")]
+use crate::{EndpointStream, TcpConnector};
 
 #[derive(Debug, Clone)]
 pub struct ESLConfig {
+    /// Global event socket password, or the user's password when `user` is set
     pub password: String,
+    /// Authenticate as `user@domain` via `userauth` instead of the global password
+    pub user: Option<String>,
+    pub timeout: Duration,
+    /// Event subscription sent by [`Inbound::connect`] once authenticated
+    pub events: Option<EventSubscription>,
+    /// Detect unresponsive peers whilst waiting on [`ESLConnection::recv`]
+    pub keepalive: Option<Keepalive>,
+    /// TCP keepalive idle time set on sockets opened by [`Inbound::connect`]
+    pub tcp_keepalive: Option<Duration>,
+    /// Default limit for [`ESLConnection::send_recv`], unlimited if `None`
+    pub command_timeout: Option<Duration>,
+    /// Default limit for [`ESLConnection::shutdown`] and [`ESLConnection::disconnect`]
+    pub disconnect_timeout: Duration,
+    /// Bounds on frames received from the peer
+    pub limits: CodecLimits,
+}
+
+/// Liveness monitoring for an [`ESLConnection`].
+///
+/// Any frame received from the peer counts as activity, so subscribing to
+/// `HEARTBEAT` events avoids most probes. Once the connection has been idle for
+/// `interval`, an `api status` probe is sent and the peer has `timeout` to reply,
+/// otherwise the connection fails with [`ErrorKind::Timeout`](crate::ErrorKind::Timeout).
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "tokio")]
+/// use eslrs::{ESLConfig, Inbound, Keepalive};
+/// use std::time::Duration;
+///
+/// # #[cfg(feature = "tokio")]
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let config = ESLConfig {
+///     password: "ClueCon".to_string(),
+///     keepalive: Some(Keepalive {
+///         interval: Duration::from_secs(30),
+///         timeout: Duration::from_secs(5),
+///     }),
+///     ..Default::default()
+/// };
+/// let mut conn = Inbound::connect("0.0.0.0:8021", config).await.unwrap();
+/// # }
+/// # #[cfg(not(feature = "tokio"))]
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keepalive {
+    pub interval: Duration,
     pub timeout: Duration,
 }
 
@@ -22,18 +77,44 @@ impl Default for ESLConfig {
     fn default() -> Self {
         Self {
             password: "".to_string(),
+            user: None,
             timeout: Duration::from_secs(5),
+            events: None,
+            keepalive: None,
+            tcp_keepalive: None,
+            command_timeout: None,
+            disconnect_timeout: Duration::from_secs(5),
+            limits: CodecLimits::default(),
         }
     }
 }
 
+/// A password, or a connection url such as `esl://:ClueCon@fs1:8021`.
+///
+/// Strings starting with `esl://`, `esls://` or `unix://` are parsed as an
+/// [`ESLUrl`] and its credentials and query parameters are used, the address
+/// passed to `connect` still decides where to connect. A string that isn't a
+/// valid url is used as the password as is.
 impl<T> From<T> for ESLConfig
 where
     T: Into<String>,
 {
     fn from(value: T) -> Self {
+        let value = value.into();
+        if ["esl://", "esls://", "unix://"]
+            .iter()
+            .any(|scheme| value.starts_with(scheme))
+        {
+            match value.parse::<ESLUrl>() {
+                Ok(url) => return url.config,
+                Err(_e) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(error = %_e, "invalid connection url, using it as the password");
+                }
+            }
+        }
         Self {
-            password: value.into(),
+            password: value,
             ..Default::default()
         }
     }
@@ -42,12 +123,21 @@ where
 #[derive(Debug)]
 pub enum ConnectError {
     Timeout,
-    Auth,
+    /// Authentication was refused, contains the server's `Reply-Text`
+    Auth(String),
+    /// Connection was refused by the server's ACL (`text/rude-rejection`),
+    /// contains the rejection reason
+    Rejected(String),
+    /// Subscribing to [`ESLConfig::events`] was refused, contains the server's `Reply-Text`
+    Subscribe(String),
+    /// Connection url could not be parsed
+    Url(UrlError),
     Connection(ESLError),
 }
 
-impl From<Elapsed> for ConnectError {
-    fn from(_: Elapsed) -> Self {
+#[cfg(feature = "tokio")]
+impl From<tokio::time::error::Elapsed> for ConnectError {
+    fn from(_: tokio::time::error::Elapsed) -> Self {
         ConnectError::Timeout
     }
 }
@@ -56,6 +146,11 @@ impl From<ESLError> for ConnectError {
         ConnectError::Connection(value)
     }
 }
+impl From<UrlError> for ConnectError {
+    fn from(value: UrlError) -> Self {
+        ConnectError::Url(value)
+    }
+}
 impl From<std::io::Error> for ConnectError {
     fn from(value: std::io::Error) -> Self {
         Self::Connection(ESLError::from(value))
@@ -68,15 +163,22 @@ impl Display for ConnectError {
     }
 }
 
+#[cfg(feature = "tokio")]
 pub struct Inbound<T = TcpStream>(ESLConnection<T>);
+#[cfg(not(feature = "tokio"))]
+pub struct Inbound<T>(ESLConnection<T>);
 
+#[cfg(feature = "tokio")]
 impl Inbound<TcpStream> {
     /// Connects to a FreeSWITCH Event Socket and authenticates.
     ///
     /// This is the primary entry point for inbound ESL connections. It will:
     /// 1. Establish a TCP connection to the FreeSWITCH event socket
-    /// 2. Automatically authenticate with the provided password
-    /// 3. Return an authenticated connection ready for use
+    /// 2. Wait for the server's `auth/request` greeting
+    /// 3. Automatically authenticate with the provided password,
+    ///    using `userauth` when [`ESLConfig::user`] is set
+    /// 4. Subscribe to [`ESLConfig::events`], if any
+    /// 5. Return an authenticated connection ready for use
     ///
     /// # Arguments
     ///
@@ -99,8 +201,22 @@ impl Inbound<TcpStream> {
     /// let config = ESLConfig {
     ///     password: "ClueCon".to_string(),
     ///     timeout: Duration::from_secs(10),
+    ///     ..Default::default()
     /// };
     /// let mut conn = Inbound::connect("0.0.0.0:8021", config).await.unwrap();
+    ///
+    /// // Or as a directory user, restricted by their esl-allowed-* params
+    /// let config = ESLConfig {
+    ///     user: Some("1000@default".to_string()),
+    ///     password: "1234".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// let mut conn = Inbound::connect("0.0.0.0:8021", config).await.unwrap();
+    ///
+    /// // Or with the credentials and options of a connection url
+    /// let mut conn = Inbound::connect("0.0.0.0:8021", "esl://:ClueCon@fs1?timeout=10s")
+    ///     .await
+    ///     .unwrap();
     /// # }
     /// ```
     pub async fn connect<U: ToSocketAddrs, V: Into<ESLConfig>>(
@@ -108,19 +224,38 @@ impl Inbound<TcpStream> {
         config: V,
     ) -> Result<Inbound<TcpStream>, ConnectError> {
         let config: ESLConfig = config.into();
-        let stream = timeout(config.timeout, TcpStream::connect(addr)).await??;
-        let mut conn = Inbound::new(stream);
-        if conn.auth(&config.password).await.is_ok() {
-            Ok(conn)
-        } else {
-            Err(ConnectError::Auth)
-        }
+        let connector = TcpConnector::new(addr).keepalive(config.tcp_keepalive);
+        Inbound::connect_with(connector, config).await
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Inbound<EndpointStream> {
+    /// Connects using a connection url and authenticates.
+    ///
+    /// See [`ESLUrl`] for the supported url forms.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use eslrs::Inbound;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let url = std::env::var("ESL_URL").unwrap_or("esl://:ClueCon@127.0.0.1:8021".to_string());
+    /// let mut conn = Inbound::connect_url(&url).await.unwrap();
+    /// # }
+    /// ```
+    pub async fn connect_url(url: &str) -> Result<Inbound<EndpointStream>, ConnectError> {
+        let url: ESLUrl = url.parse()?;
+        let config = url.config.clone();
+        Inbound::connect_with(url, config).await
     }
 }
 
 impl<T> Inbound<T>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: Unpin,
 {
     /// Creates a new inbound connection from an existing stream.
     ///
@@ -130,30 +265,151 @@ where
     /// # Examples
     ///
     /// ```no_run
+    /// # #[cfg(feature = "tokio")]
     /// use eslrs::Inbound;
+    /// # #[cfg(feature = "tokio")]
     /// use tokio::net::TcpStream;
     ///
+    /// # #[cfg(feature = "tokio")]
     /// # #[tokio::main(flavor = "current_thread")]
     /// # async fn main() {
     /// let stream = TcpStream::connect("0.0.0.0:8021").await.unwrap();
     /// let mut conn = Inbound::new(stream);
     /// # }
+    /// # #[cfg(not(feature = "tokio"))]
+    /// # fn main() {}
     /// ```
-    pub fn new(stream: T) -> Self {
+    pub fn new<R: Runtime>(stream: T) -> Self
+    where
+        T: Transport<R>,
+    {
         Inbound(ESLConnection::new(stream))
     }
 
+    /// Connects using a custom [`Connector`] and authenticates.
+    ///
+    /// Applies the same timeout, greeting and auth handling as [`Inbound::connect`]
+    /// regardless of the transport.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "tokio")]
+    /// use eslrs::{Inbound, TcpConnector};
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let mut conn = Inbound::connect_with(TcpConnector::new("0.0.0.0:8021"), "ClueCon")
+    ///     .await
+    ///     .unwrap();
+    /// # }
+    /// # #[cfg(not(feature = "tokio"))]
+    /// # fn main() {}
+    /// ```
+    pub async fn connect_with<C, V, R>(connector: C, config: V) -> Result<Inbound<T>, ConnectError>
+    where
+        C: Connector<Stream = T>,
+        V: Into<ESLConfig>,
+        R: Runtime,
+        T: Transport<R>,
+    {
+        let config: ESLConfig = config.into();
+        let stream = Driver::<T>::new::<R>()
+            .timeout(config.timeout, connector.connect())
+            .await
+            .ok_or(ConnectError::Timeout)??;
+        Inbound::handshake(stream, config).await
+    }
+
+    /// Performs the inbound handshake over an already connected stream.
+    ///
+    /// Waits for the server's `auth/request` greeting and then authenticates,
+    /// see [`Inbound::connect`]. Handshake frames are consumed and never
+    /// delivered to [`ESLConnection::recv`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "tokio")]
+    /// use eslrs::Inbound;
+    /// # #[cfg(feature = "tokio")]
+    /// use tokio::net::TcpStream;
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let stream = TcpStream::connect("0.0.0.0:8021").await.unwrap();
+    /// let mut conn = Inbound::handshake(stream, "ClueCon").await.unwrap();
+    /// # }
+    /// # #[cfg(not(feature = "tokio"))]
+    /// # fn main() {}
+    /// ```
+    pub async fn handshake<V, R>(stream: T, config: V) -> Result<Inbound<T>, ConnectError>
+    where
+        V: Into<ESLConfig>,
+        R: Runtime,
+        T: Transport<R>,
+    {
+        let config: ESLConfig = config.into();
+        let mut conn = Inbound(ESLConnection::with_config(stream, &config));
+        conn.driver()
+            .timeout(config.timeout, conn.authenticate(&config))
+            .await
+            .ok_or(ConnectError::Timeout)??;
+        Ok(conn)
+    }
+
+    async fn authenticate(&mut self, config: &ESLConfig) -> Result<(), ConnectError> {
+        let greeting = self.recv().await?;
+        match greeting.get_content_type() {
+            Some("auth/request") => {}
+            Some("text/rude-rejection") => {
+                let reason = greeting
+                    .get_body()
+                    .map(|b| String::from_utf8_lossy(b).trim().to_string())
+                    .unwrap_or_default();
+                return Err(ConnectError::Rejected(reason));
+            }
+            other => {
+                let text = format!("unexpected greeting: {}", other.unwrap_or_default());
+                return Err(ConnectError::Auth(text));
+            }
+        }
+
+        let reply = match &config.user {
+            Some(user) => self.userauth(user, &config.password).await?,
+            None => self.auth(&config.password).await?,
+        };
+        if !reply.is_ok() {
+            let text = reply.get_header("Reply-Text").unwrap_or_default();
+            return Err(ConnectError::Auth(text.to_string()));
+        }
+
+        if let Some(events) = &config.events {
+            let reply = self.subscribe(events).await?;
+            if !reply.is_ok() {
+                let text = reply.get_header("Reply-Text").unwrap_or_default();
+                return Err(ConnectError::Subscribe(text.to_string()));
+            }
+        }
+        Ok(())
+    }
+
     /// Authenticates with FreeSWITCH using the provided password.
     ///
-    /// Called automatically by [`Inbound::connect`]. Only needed when using
-    /// [`Inbound::new`] with a custom stream.
+    /// Called automatically by [`Inbound::connect`] and [`Inbound::handshake`].
+    /// Only needed when using [`Inbound::new`] with a custom stream.
     ///
     /// # Examples
     ///
     /// ```no_run
+    /// # #[cfg(feature = "tokio")]
     /// use eslrs::{Inbound, ESLError};
+    /// # #[cfg(feature = "tokio")]
     /// use tokio::net::TcpStream;
     ///
+    /// # #[cfg(feature = "tokio")]
     /// # #[tokio::main(flavor = "current_thread")]
     /// # async fn main() {
     /// let stream = TcpStream::connect("0.0.0.0:8021").await.unwrap();
@@ -161,10 +417,43 @@ where
     /// let reply = conn.auth("ClueCon").await.unwrap();
     /// assert!(reply.is_ok());
     /// # }
+    /// # #[cfg(not(feature = "tokio"))]
+    /// # fn main() {}
     /// ```
     pub async fn auth(&mut self, password: &str) -> Result<Reply, ESLError> {
         self.send_recv(&format!("auth {}", password)).await
     }
+
+    /// Authenticates as a directory user using `userauth`.
+    ///
+    /// The user is given as `user@domain`, their access is then limited by the
+    /// `esl-allowed-api`, `esl-allowed-events` and `esl-allowed-log` user params.
+    /// Called automatically by [`Inbound::connect`] and [`Inbound::handshake`]
+    /// when [`ESLConfig::user`] is set.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "tokio")]
+    /// use eslrs::Inbound;
+    /// # #[cfg(feature = "tokio")]
+    /// use tokio::net::TcpStream;
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let stream = TcpStream::connect("0.0.0.0:8021").await.unwrap();
+    /// let mut conn = Inbound::new(stream);
+    /// let reply = conn.userauth("1000@default", "1234").await.unwrap();
+    /// assert!(reply.is_ok());
+    /// # }
+    /// # #[cfg(not(feature = "tokio"))]
+    /// # fn main() {}
+    /// ```
+    pub async fn userauth(&mut self, user: &str, password: &str) -> Result<Reply, ESLError> {
+        self.send_recv(&format!("userauth {}:{}", user, password))
+            .await
+    }
 }
 
 impl<T> Deref for Inbound<T> {
@@ -181,14 +470,20 @@ impl<T> DerefMut for Inbound<T> {
 
 // =============
 
+#[cfg(feature = "tokio")]
 pub struct Outbound<T = TcpStream> {
     conn: ESLConnection<T>,
     info: Reply,
 }
+#[cfg(not(feature = "tokio"))]
+pub struct Outbound<T> {
+    conn: ESLConnection<T>,
+    info: Reply,
+}
 
 impl<T> Outbound<T>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: Unpin,
 {
     /// Performs the outbound socket handshake with FreeSWITCH.
     ///
@@ -199,9 +494,12 @@ where
     /// # Examples
     ///
     /// ```no_run
+    /// # #[cfg(feature = "tokio")]
     /// use eslrs::{Outbound, ESLConfig};
+    /// # #[cfg(feature = "tokio")]
     /// use tokio::net::TcpListener;
     ///
+    /// # #[cfg(feature = "tokio")]
     /// # #[tokio::main(flavor = "current_thread")]
     /// # async fn main() {
     /// let listener = TcpListener::bind("0.0.0.0:8888").await.unwrap();
@@ -220,10 +518,22 @@ where
     ///     });
     /// }
     /// # }  
+    /// # #[cfg(not(feature = "tokio"))]
+    /// # fn main() {}
     /// ```
-    pub async fn handshake(stream: T, config: ESLConfig) -> Result<Outbound<T>, ConnectError> {
-        let mut conn = ESLConnection::new(stream);
-        let info = timeout(config.timeout, conn.send_recv("connect")).await??;
+    pub async fn handshake<V, R>(stream: T, config: V) -> Result<Outbound<T>, ConnectError>
+    where
+        V: Into<ESLConfig>,
+        R: Runtime,
+        T: Transport<R>,
+    {
+        let config: ESLConfig = config.into();
+        let mut conn = ESLConnection::with_config(stream, &config);
+        let info = conn
+            .driver()
+            .timeout(config.timeout, conn.send_recv("connect"))
+            .await
+            .ok_or(ConnectError::Timeout)??;
         Ok(Outbound { conn, info })
     }
 
@@ -235,7 +545,9 @@ where
     /// # Examples
     ///
     /// ```no_run
+    /// # #[cfg(feature = "tokio")]
     /// use eslrs::{Outbound, ESLConfig};
+    /// # #[cfg(feature = "tokio")]
     /// # async fn example(conn: Outbound<tokio::net::TcpStream>) {
     /// let info = conn.get_info();
     /// if let Some(uuid) = info.get_header("Unique-ID") {
@@ -262,3 +574,112 @@ impl<T> DerefMut for Outbound<T> {
         &mut self.conn
     }
 }
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+    use tokio_test::io::Builder;
+
+    // Accepts a single connection, records the auth command and replies with `reply_text`
+    async fn auth_server(reply_text: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let handle = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut socket = BufReader::new(socket);
+            socket
+                .write_all(b"Content-Type: auth/request\n\n")
+                .await
+                .unwrap();
+            let mut line = String::new();
+            socket.read_line(&mut line).await.unwrap();
+            let reply = format!("Content-Type: command/reply\nReply-Text: {reply_text}\n\n");
+            socket.write_all(reply.as_bytes()).await.unwrap();
+            line.trim().to_string()
+        });
+        (addr, handle)
+    }
+
+    #[tokio::test]
+    async fn test_connect_password_auth() {
+        let (addr, server) = auth_server("+OK accepted").await;
+        let conn = Inbound::connect(addr, "ClueCon").await;
+        assert!(conn.is_ok(), "{:?}", conn.err());
+        assert_eq!(server.await.unwrap(), "auth ClueCon");
+    }
+
+    #[tokio::test]
+    async fn test_connect_userauth() {
+        let (addr, server) = auth_server("+OK accepted").await;
+        let config = ESLConfig {
+            user: Some("1000@default".to_string()),
+            password: "1234".to_string(),
+            ..Default::default()
+        };
+        let conn = Inbound::connect(addr, config).await;
+        assert!(conn.is_ok(), "{:?}", conn.err());
+        assert_eq!(server.await.unwrap(), "userauth 1000@default:1234");
+    }
+
+    #[tokio::test]
+    async fn test_connect_config_from_url() {
+        let (addr, server) = auth_server("+OK accepted").await;
+        let conn = Inbound::connect(addr, "esl://1000@default:1234@fs1:8021?timeout=2s").await;
+        assert!(conn.is_ok(), "{:?}", conn.err());
+        assert_eq!(server.await.unwrap(), "userauth 1000@default:1234");
+
+        let config = ESLConfig::from("esl://:ClueCon@fs1?timeout=2s");
+        assert_eq!(config.password, "ClueCon");
+        assert_eq!(config.timeout, Duration::from_secs(2));
+        assert_eq!(ESLConfig::from("esl://:%zz@fs1").password, "esl://:%zz@fs1");
+    }
+
+    #[tokio::test]
+    async fn test_handshake_consumes_greeting() {
+        let stream = Builder::new()
+            .read(b"Content-Type: auth/request\n\n")
+            .write(b"auth ClueCon\r\n\r\n")
+            .read(b"Content-Type: command/reply\nReply-Text: +OK accepted\n\n")
+            .read(b"Content-Type: text/event-plain\nContent-Length: 22\n\nEvent-Name: HEARTBEAT\n")
+            .build();
+        let mut conn = Inbound::handshake(stream, "ClueCon").await.unwrap();
+        let event = conn.recv().await.unwrap();
+        assert_eq!(event.get_content_type(), Some("text/event-plain"));
+    }
+
+    #[tokio::test]
+    async fn test_handshake_subscribes_events() {
+        let stream = Builder::new()
+            .read(b"Content-Type: auth/request\n\n")
+            .write(b"auth ClueCon\r\n\r\n")
+            .read(b"Content-Type: command/reply\nReply-Text: +OK accepted\n\n")
+            .write(b"event json CHANNEL_CREATE\r\n\r\n")
+            .read(b"Content-Type: command/reply\nReply-Text: -ERR no permission\n\n")
+            .build();
+        let url: ESLUrl = "esl://:ClueCon@fs1?events=json:CHANNEL_CREATE"
+            .parse()
+            .unwrap();
+        let res = Inbound::handshake(stream, url.config).await;
+        assert!(matches!(res, Err(ConnectError::Subscribe(text)) if text == "-ERR no permission"));
+    }
+
+    #[tokio::test]
+    async fn test_handshake_rude_rejection() {
+        let stream = Builder::new()
+            .read(b"Content-Type: text/rude-rejection\nContent-Length: 24\n\nAccess Denied, go away.\n")
+            .build();
+        let res = Inbound::handshake(stream, "ClueCon").await;
+        assert!(matches!(res, Err(ConnectError::Rejected(r)) if r == "Access Denied, go away."));
+    }
+
+    #[tokio::test]
+    async fn test_connect_auth_denied_reply_text() {
+        let (addr, _server) = auth_server("-ERR invalid").await;
+        let res = Inbound::connect(addr, "wrong").await;
+        assert!(matches!(res, Err(ConnectError::Auth(text)) if text == "-ERR invalid"));
+    }
+}
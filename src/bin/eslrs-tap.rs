@@ -0,0 +1,296 @@
+// Writes every event as a JSON object per line, ie. to grep, jq or ship them.
+//
+// Plain events are converted to the json event shape, so the output is the
+// same regardless of the format subscribed with.
+//
+// Usage:
+//   # Hangups of a single call to stdout
+//   cargo run --features cli --bin eslrs-tap -- -e CHANNEL_HANGUP -f "Unique-ID abc123"
+//
+//   # Only a few headers, to files of at most 10MB, keeping 3 old ones
+//   cargo run --features cli --bin eslrs-tap -- --header Event-Name --header Unique-ID \
+//       -o events.jsonl --rotate-size 10000000 --rotate-keep 3
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    process::ExitCode,
+    time::Duration,
+};
+
+use clap::Parser;
+use eslrs::{
+    Command, ESLConfig, ESLError, Filter, Inbound,
+    event::{JsonEvent, Reply},
+};
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Dump FreeSWITCH events as JSON lines")]
+struct Args {
+    /// Host to connect to
+    #[arg(short = 'H', long, default_value = "127.0.0.1")]
+    host: String,
+    /// Port to connect to
+    #[arg(short = 'P', long, default_value_t = 8021)]
+    port: u16,
+    /// Event socket password
+    #[arg(short, long, default_value = "ClueCon")]
+    password: String,
+    /// Authenticate as user@domain instead of the global password
+    #[arg(short, long)]
+    user: Option<String>,
+    /// Events to subscribe to, ie. "CHANNEL_CREATE CUSTOM sofia::register"
+    #[arg(short, long, default_value = "ALL")]
+    events: String,
+    /// Subscribe to plain events instead of json
+    #[arg(long)]
+    plain: bool,
+    /// Server side filter as "<header> <value>", repeatable
+    #[arg(short, long = "filter", value_name = "FILTER")]
+    filters: Vec<String>,
+    /// Only write these headers, repeatable, `_body` for the body
+    #[arg(long = "header", value_name = "HEADER")]
+    headers: Vec<String>,
+    /// File to write to instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Rotate the output file once it would grow beyond this many bytes
+    #[arg(long, requires = "output")]
+    rotate_size: Option<u64>,
+    /// Rotated files to keep, as <output>.1 to <output>.N
+    #[arg(long, default_value_t = 5)]
+    rotate_keep: usize,
+    /// Seconds to wait for the connection and each reply
+    #[arg(short = 'T', long, default_value_t = 10)]
+    timeout: u64,
+}
+
+/// Line oriented output, optionally rotated by size.
+struct Output {
+    writer: Box<dyn Write>,
+    file: Option<Rotation>,
+}
+
+struct Rotation {
+    path: PathBuf,
+    written: u64,
+    max_size: Option<u64>,
+    keep: usize,
+}
+
+impl Output {
+    fn stdout() -> Self {
+        Self {
+            writer: Box::new(io::stdout().lock()),
+            file: None,
+        }
+    }
+
+    fn file(path: PathBuf, max_size: Option<u64>, keep: usize) -> io::Result<Self> {
+        let file = File::options().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            writer: Box::new(BufWriter::new(file)),
+            file: Some(Rotation {
+                path,
+                written,
+                max_size,
+                keep,
+            }),
+        })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if let Some(rotation) = &mut self.file {
+            if let Some(max) = rotation.max_size
+                && rotation.written > 0
+                && rotation.written + len > max
+            {
+                self.writer.flush()?;
+                self.writer = Box::new(BufWriter::new(rotation.rotate()?));
+            }
+            rotation.written += len;
+        }
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        // Keep the output usable with `tail -f` while idle
+        self.writer.flush()
+    }
+}
+
+impl Rotation {
+    // events.jsonl.1 -> events.jsonl.2, events.jsonl -> events.jsonl.1
+    fn rotate(&mut self) -> io::Result<File> {
+        let rotated = |n: usize| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{n}"));
+            PathBuf::from(name)
+        };
+        if self.keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for n in (1..self.keep).rev() {
+                match fs::rename(rotated(n), rotated(n + 1)) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                    _ => {}
+                }
+            }
+            fs::rename(&self.path, rotated(1))?;
+        }
+        self.written = 0;
+        File::create(&self.path)
+    }
+}
+
+fn parse_filter(filter: &str) -> Result<Filter, String> {
+    match filter.trim().split_once(' ') {
+        Some((header, value)) => Ok(Filter::new(header, value.trim())),
+        None => Err(format!(
+            "invalid filter `{filter}`, expected \"<header> <value>\""
+        )),
+    }
+}
+
+// Refused commands as errors, with the server's Reply-Text
+fn check(reply: Result<Reply, ESLError>) -> Result<(), String> {
+    match reply {
+        Ok(reply) if reply.is_ok() => Ok(()),
+        Ok(reply) => Err(reply
+            .get_header("Reply-Text")
+            .unwrap_or_default()
+            .to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Keeps only the allowed headers, matched ignoring ASCII case.
+fn retain_headers(json: &mut JsonEvent, allowed: &[String]) {
+    if allowed.is_empty() {
+        return;
+    }
+    if let Some(map) = json.as_object_mut() {
+        map.retain(|k, _| allowed.iter().any(|h| h.eq_ignore_ascii_case(k)));
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let filters: Vec<Filter> = match args.filters.iter().map(|f| parse_filter(f)).collect() {
+        Ok(filters) => filters,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(2);
+        }
+    };
+    let output = match &args.output {
+        Some(path) => Output::file(path.clone(), args.rotate_size, args.rotate_keep),
+        None => Ok(Output::stdout()),
+    };
+    let mut output = match output {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Error opening output: {e}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let timeout = Duration::from_secs(args.timeout);
+    let config = ESLConfig {
+        password: args.password,
+        user: args.user,
+        timeout,
+        command_timeout: Some(timeout),
+        ..Default::default()
+    };
+    let mut conn = match Inbound::connect((args.host.as_str(), args.port), config).await {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("Error connecting to {}:{}: {e}", args.host, args.port);
+            return ExitCode::from(2);
+        }
+    };
+
+    let subscribe = match args.plain {
+        true => Command::events(args.events.as_str()),
+        false => Command::events_json(args.events.as_str()),
+    };
+    if let Err(e) = check(conn.send_recv(subscribe).await) {
+        eprintln!("Error subscribing to {}: {e}", args.events);
+        return ExitCode::FAILURE;
+    }
+    for filter in &filters {
+        if let Err(e) = check(conn.add_filter(filter).await) {
+            eprintln!("Error adding filter {filter:?}: {e}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    loop {
+        let event = match conn.recv().await {
+            Ok(event) => event,
+            Err(e) => {
+                eprintln!("{e}");
+                return ExitCode::FAILURE;
+            }
+        };
+        // Frames that aren't events, ie. log lines, are skipped
+        let Some(mut json) = event.to_json() else {
+            continue;
+        };
+        retain_headers(&mut json, &args.headers);
+        if let Err(e) = output.write_line(&json.to_string()) {
+            eprintln!("Error writing event: {e}");
+            return ExitCode::FAILURE;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_retain_headers() {
+        let mut event = json!({"Event-Name": "HEARTBEAT", "Up-Time": "1 day", "_body": "x"});
+        retain_headers(&mut event, &[]);
+        assert_eq!(event["Up-Time"], "1 day");
+
+        retain_headers(&mut event, &["event-name".to_string(), "_body".to_string()]);
+        assert_eq!(event, json!({"Event-Name": "HEARTBEAT", "_body": "x"}));
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        assert_eq!(
+            parse_filter("Event-Name CHANNEL_HANGUP"),
+            Ok(Filter::new("Event-Name", "CHANNEL_HANGUP"))
+        );
+        assert!(parse_filter("Event-Name").is_err());
+    }
+
+    #[test]
+    fn test_rotation() {
+        let dir = std::env::temp_dir().join(format!("eslrs-tap-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("events.jsonl");
+
+        let mut output = Output::file(path.clone(), Some(4), 2).unwrap();
+        for line in ["one", "two", "three", "four", "five"] {
+            output.write_line(line).unwrap();
+        }
+        drop(output);
+
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("events.jsonl"), "five\n");
+        assert_eq!(read("events.jsonl.1"), "four\n");
+        assert_eq!(read("events.jsonl.2"), "three\n");
+        assert!(!dir.join("events.jsonl.3").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,406 @@
+// Command line client in the spirit of `fs_cli`.
+//
+// Usage:
+//   # Interactive, type api commands or /help
+//   cargo run --features cli -- -H 127.0.0.1 -p ClueCon
+//
+//   # Run commands and exit, non zero if any failed
+//   cargo run --features cli -- -x "show channels" -x "status"
+
+use std::{path::PathBuf, process::ExitCode, str::FromStr, sync::mpsc as std_mpsc, time::Duration};
+
+use clap::Parser;
+use eslrs::{
+    Command, ESLConfig, ESLError, EventName, EventSubscription, Filter, Inbound,
+    SubscriptionFormat,
+    event::{Event, JsonEvent, PlainEvent, Reply, percent_decode},
+};
+use rustyline::{DefaultEditor, ExternalPrinter, error::ReadlineError};
+use tokio::{net::TcpStream, sync::mpsc};
+
+#[derive(Parser, Debug)]
+#[command(version, about = "FreeSWITCH event socket client")]
+struct Args {
+    /// Host to connect to
+    #[arg(short = 'H', long, default_value = "127.0.0.1")]
+    host: String,
+    /// Port to connect to
+    #[arg(short = 'P', long, default_value_t = 8021)]
+    port: u16,
+    /// Event socket password
+    #[arg(short, long, default_value = "ClueCon")]
+    password: String,
+    /// Authenticate as user@domain instead of the global password
+    #[arg(short, long)]
+    user: Option<String>,
+    /// Api command to run, repeatable, exits after running them
+    #[arg(short = 'x', long = "execute", value_name = "COMMAND")]
+    execute: Vec<String>,
+    /// Seconds to wait for the connection and each reply
+    #[arg(short = 'T', long, default_value_t = 10)]
+    timeout: u64,
+}
+
+const HELP: &str = "\
+Type an api command, ie. `status`, or one of:
+  /event [plain|json] <names..> [CUSTOM <subclasses..>]
+  /nixevent <names..> [CUSTOM <subclasses..>]
+  /noevents
+  /filter <header> <value|/regex/>
+  /filter delete [<header> <value|/regex/>]
+  /log [level]
+  /nolog
+  /help
+  /exit, /quit, /bye
+";
+
+#[derive(Debug, PartialEq)]
+enum Input {
+    Empty,
+    Api(String),
+    Event(EventSubscription),
+    NixEvent(EventSubscription),
+    NoEvents,
+    Filter(Filter),
+    /// `None` deletes every filter
+    FilterDelete(Option<Filter>),
+    Log(String),
+    NoLog,
+    Help,
+    Exit,
+}
+
+fn parse_line(line: &str) -> Result<Input, String> {
+    let line = line.trim();
+    let Some(slash) = line.strip_prefix('/') else {
+        return Ok(match line {
+            "" => Input::Empty,
+            _ => Input::Api(line.to_string()),
+        });
+    };
+    let (cmd, rest) = slash.split_once(' ').unwrap_or((slash, ""));
+    let rest = rest.trim();
+    match cmd {
+        "event" => {
+            let (format, names) = match rest.split_once(' ').unwrap_or((rest, "")) {
+                ("plain", names) => (SubscriptionFormat::Plain, names),
+                ("json", names) => (SubscriptionFormat::Json, names),
+                _ => (SubscriptionFormat::Plain, rest),
+            };
+            Ok(Input::Event(parse_events(names)?.format(format)))
+        }
+        "nixevent" => Ok(Input::NixEvent(parse_events(rest)?)),
+        "noevents" => Ok(Input::NoEvents),
+        "filter" => match rest.split_once(' ').unwrap_or((rest, "")) {
+            ("delete", "") => Ok(Input::FilterDelete(None)),
+            ("delete", filter) => Ok(Input::FilterDelete(Some(parse_filter(filter)?))),
+            _ => Ok(Input::Filter(parse_filter(rest)?)),
+        },
+        "log" => Ok(Input::Log(match rest {
+            "" => "debug".to_string(),
+            level => level.to_string(),
+        })),
+        "nolog" => Ok(Input::NoLog),
+        "help" => Ok(Input::Help),
+        "exit" | "quit" | "bye" => Ok(Input::Exit),
+        _ => Err(format!("unknown command /{cmd}, see /help")),
+    }
+}
+
+// Event names, with any names after `CUSTOM` taken as subclasses
+fn parse_events(names: &str) -> Result<EventSubscription, String> {
+    let mut sub = EventSubscription::new();
+    let mut names = names.split_whitespace();
+    for name in names.by_ref() {
+        if name.eq_ignore_ascii_case("CUSTOM") {
+            break;
+        }
+        let name = EventName::from_str(&name.to_ascii_uppercase()).map_err(|e| e.to_string())?;
+        sub = sub.events([name]);
+    }
+    sub = sub.custom(names);
+    match sub.is_empty() {
+        true => Err("no events given".to_string()),
+        false => Ok(sub),
+    }
+}
+
+fn parse_filter(filter: &str) -> Result<Filter, String> {
+    let Some((header, value)) = filter.split_once(' ') else {
+        return Err("usage: /filter <header> <value>".to_string());
+    };
+    let value = value.trim();
+    match value.strip_prefix('/').and_then(|v| v.strip_suffix('/')) {
+        Some(pattern) => Ok(Filter::regex(header, pattern)),
+        None => Ok(Filter::new(header, value)),
+    }
+}
+
+fn lossy(body: Option<&eslrs::event::Bytes>) -> String {
+    body.map(|b| String::from_utf8_lossy(b).into_owned())
+        .unwrap_or_default()
+}
+
+// The api output, or the Reply-Text of other commands
+fn reply_text(reply: &Reply) -> String {
+    match reply.get_content_type() {
+        Some("api/response") => lossy(reply.get_body()),
+        _ => format!("{}\n", reply.get_header("Reply-Text").unwrap_or_default()),
+    }
+}
+
+// `-ERR` and `-USAGE` api output comes back as a successful api/response
+fn succeeded(reply: &Reply) -> bool {
+    let text = reply_text(reply);
+    reply.is_ok() && !text.starts_with("-ERR") && !text.starts_with("-USAGE")
+}
+
+fn format_event(event: &Event) -> String {
+    if event.is_json()
+        && let Ok(json) = JsonEvent::try_from(event.clone())
+    {
+        return serde_json::to_string_pretty(&json).unwrap_or_default() + "\n";
+    }
+    if event.is_plain_event()
+        && let Ok(plain) = PlainEvent::try_from(event.clone())
+    {
+        let mut out = String::new();
+        for (name, value) in plain.headers() {
+            out += &format!("{name}: {}\n", percent_decode(value));
+        }
+        if let Some(body) = plain.get_body() {
+            out += &format!("\n{}", String::from_utf8_lossy(body));
+        }
+        return out;
+    }
+    match event.get_content_type() {
+        Some("log/data") | Some("text/disconnect-notice") => lossy(event.get_body()),
+        content_type => format!(
+            "[{}]\n{}",
+            content_type.unwrap_or("unknown"),
+            lossy(event.get_body())
+        ),
+    }
+}
+
+async fn execute(conn: &mut Inbound<TcpStream>, commands: &[String]) -> ExitCode {
+    let mut code = ExitCode::SUCCESS;
+    for cmd in commands {
+        match conn.send_recv(Command::api(cmd.as_str())).await {
+            Ok(reply) => {
+                print!("{}", reply_text(&reply));
+                if !succeeded(&reply) {
+                    code = ExitCode::FAILURE;
+                }
+            }
+            Err(e) => {
+                eprintln!("{cmd}: {e}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+    conn.disconnect().await;
+    code
+}
+
+async fn run_input(conn: &mut Inbound<TcpStream>, input: Input) -> Result<String, ESLError> {
+    let reply = match input {
+        Input::Empty => return Ok(String::new()),
+        Input::Help => return Ok(HELP.to_string()),
+        Input::Exit => unreachable!("handled by the caller"),
+        Input::Api(cmd) => conn.send_recv(Command::api(cmd)).await?,
+        Input::Event(sub) => conn.subscribe(&sub).await?,
+        Input::NixEvent(sub) => conn.unsubscribe(&sub).await?,
+        Input::NoEvents => conn.unsubscribe_all().await?,
+        Input::Filter(filter) => conn.add_filter(&filter).await?,
+        Input::FilterDelete(Some(filter)) => conn.delete_filter(&filter).await?,
+        Input::FilterDelete(None) => conn.delete_all_filters().await?,
+        Input::Log(level) => conn.send_recv(Command::log(level)).await?,
+        Input::NoLog => conn.send_recv(Command::nolog()).await?,
+    };
+    Ok(reply_text(&reply))
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".eslrs_history"))
+}
+
+// Reads lines on its own thread, each line waits for `done` before the
+// next prompt so command output isn't interleaved with it.
+fn spawn_readline(
+    mut rl: DefaultEditor,
+    lines: mpsc::Sender<String>,
+    done: std_mpsc::Receiver<()>,
+) {
+    std::thread::spawn(move || {
+        let history = history_path();
+        if let Some(path) = &history {
+            let _ = rl.load_history(path);
+        }
+        loop {
+            let line = match rl.readline("eslrs> ") {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(_) => break,
+            };
+            if !line.trim().is_empty() {
+                let _ = rl.add_history_entry(line.as_str());
+                if let Some(path) = &history {
+                    let _ = rl.append_history(path);
+                }
+            }
+            if lines.blocking_send(line).is_err() || done.recv().is_err() {
+                break;
+            }
+        }
+    });
+}
+
+async fn interactive(mut conn: Inbound<TcpStream>) -> ExitCode {
+    let mut rl = match DefaultEditor::new() {
+        Ok(rl) => rl,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    // Events arriving while at the prompt are printed above it
+    let mut printer: Box<dyn FnMut(String) + Send> = match rl.create_external_printer() {
+        Ok(mut p) => Box::new(move |s| {
+            let _ = p.print(s);
+        }),
+        Err(_) => Box::new(|s| print!("{s}")),
+    };
+
+    let (line_tx, mut lines) = mpsc::channel(1);
+    let (done, done_rx) = std_mpsc::channel();
+    spawn_readline(rl, line_tx, done_rx);
+
+    let code = loop {
+        tokio::select! {
+            line = lines.recv() => {
+                let Some(line) = line else { break ExitCode::SUCCESS };
+                match parse_line(&line) {
+                    Ok(Input::Exit) => break ExitCode::SUCCESS,
+                    Ok(input) => match run_input(&mut conn, input).await {
+                        Ok(out) => printer(out),
+                        Err(e) if conn.is_disconnected() => {
+                            printer(format!("{e}\n"));
+                            break ExitCode::FAILURE;
+                        }
+                        Err(e) => printer(format!("{e}\n")),
+                    },
+                    Err(e) => printer(format!("{e}\n")),
+                }
+                let _ = done.send(());
+            }
+            event = conn.recv() => match event {
+                Ok(event) => printer(format_event(&event)),
+                Err(e) => {
+                    // Let the prompt return before exiting, so the terminal is restored
+                    printer(format!("{e}, press enter to exit\n"));
+                    drop(done);
+                    lines.recv().await;
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+    };
+    if !conn.is_disconnected() {
+        conn.disconnect().await;
+    }
+    code
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+    let timeout = Duration::from_secs(args.timeout);
+    let config = ESLConfig {
+        password: args.password,
+        user: args.user,
+        timeout,
+        command_timeout: Some(timeout),
+        ..Default::default()
+    };
+
+    let mut conn = match Inbound::connect((args.host.as_str(), args.port), config).await {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("Error connecting to {}:{}: {e}", args.host, args.port);
+            return ExitCode::from(2);
+        }
+    };
+
+    if args.execute.is_empty() {
+        interactive(conn).await
+    } else {
+        execute(&mut conn, &args.execute).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eslrs::EventName::*;
+
+    #[test]
+    fn test_parse_api() {
+        assert_eq!(parse_line("  "), Ok(Input::Empty));
+        assert_eq!(
+            parse_line("show channels\n"),
+            Ok(Input::Api("show channels".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_event() {
+        let sub = EventSubscription::new()
+            .events([ChannelCreate, ChannelHangup])
+            .custom(["sofia::register"]);
+        assert_eq!(
+            parse_line("/event channel_create CHANNEL_HANGUP CUSTOM sofia::register"),
+            Ok(Input::Event(sub.clone()))
+        );
+        assert_eq!(
+            parse_line("/event json CHANNEL_CREATE CHANNEL_HANGUP custom sofia::register"),
+            Ok(Input::Event(sub.clone().format(SubscriptionFormat::Json)))
+        );
+        assert_eq!(
+            parse_line("/nixevent CHANNEL_CREATE CHANNEL_HANGUP CUSTOM sofia::register"),
+            Ok(Input::NixEvent(sub))
+        );
+        assert!(parse_line("/event").is_err());
+        assert!(parse_line("/event NOT_AN_EVENT").is_err());
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        assert_eq!(
+            parse_line("/filter Event-Name CHANNEL_HANGUP"),
+            Ok(Input::Filter(Filter::event_name(ChannelHangup)))
+        );
+        assert_eq!(
+            parse_line("/filter Caller-Destination-Number /^9[0-9]{3}$/"),
+            Ok(Input::Filter(Filter::regex(
+                "Caller-Destination-Number",
+                "^9[0-9]{3}$"
+            )))
+        );
+        assert_eq!(
+            parse_line("/filter delete Unique-ID abc"),
+            Ok(Input::FilterDelete(Some(Filter::unique_id("abc"))))
+        );
+        assert_eq!(parse_line("/filter delete"), Ok(Input::FilterDelete(None)));
+        assert!(parse_line("/filter Unique-ID").is_err());
+    }
+
+    #[test]
+    fn test_parse_other() {
+        assert_eq!(parse_line("/log"), Ok(Input::Log("debug".to_string())));
+        assert_eq!(parse_line("/log info"), Ok(Input::Log("info".to_string())));
+        assert_eq!(parse_line("/nolog"), Ok(Input::NoLog));
+        assert_eq!(parse_line("/quit"), Ok(Input::Exit));
+        assert!(parse_line("/nope").is_err());
+    }
+}
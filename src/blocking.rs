@@ -0,0 +1,165 @@
+//! A synchronous client for tools without an async runtime.
+//!
+//! [`Inbound`] wraps the async [`crate::Inbound`] and drives it on its own
+//! current-thread tokio runtime, in the same spirit as `reqwest::blocking`.
+//! The runtime only runs while a method is being called, so keepalive
+//! probes and timeouts are only observed during calls.
+//!
+//! Enable the `blocking` feature to use this module. Calling into it from
+//! within an async runtime panics, use the async API there instead.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use eslrs::{Command, blocking::Inbound};
+//!
+//! let mut conn = Inbound::connect("0.0.0.0:8021", "ClueCon").unwrap();
+//! let reply = conn.send_recv(Command::api("status")).unwrap();
+//! println!("{:?}", reply.get_body());
+//! conn.disconnect();
+//! ```
+use std::{fmt::Debug, io, time::Duration};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpStream, ToSocketAddrs},
+    runtime::{Builder, Runtime},
+};
+
+use crate::{
+    Command, ConnectError, ESLConfig, ESLError, EndpointStream, EventMatcher, EventSubscription,
+    Filter,
+    event::{Event, Reply},
+};
+
+/// Blocking counterpart of [`crate::Inbound`].
+pub struct Inbound<T = TcpStream> {
+    // Dropped before the runtime it was created on
+    conn: crate::Inbound<T>,
+    rt: Runtime,
+}
+
+fn runtime() -> io::Result<Runtime> {
+    Builder::new_current_thread().enable_all().build()
+}
+
+impl Inbound<TcpStream> {
+    /// Connects and authenticates, see [`crate::Inbound::connect`].
+    pub fn connect<A, C>(addr: A, config: C) -> Result<Self, ConnectError>
+    where
+        A: ToSocketAddrs,
+        C: Into<ESLConfig>,
+    {
+        let rt = runtime()?;
+        let conn = rt.block_on(crate::Inbound::connect(addr, config))?;
+        Ok(Self { conn, rt })
+    }
+}
+
+impl Inbound<EndpointStream> {
+    /// Connects to an `esl://`, `esls://` or `unix://` url,
+    /// see [`crate::Inbound::connect_url`].
+    pub fn connect_url(url: &str) -> Result<Self, ConnectError> {
+        let rt = runtime()?;
+        let conn = rt.block_on(crate::Inbound::connect_url(url))?;
+        Ok(Self { conn, rt })
+    }
+}
+
+impl<T> Inbound<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Sends a command and waits for its reply.
+    pub fn send_recv<'a, C: Into<Command<'a>> + Debug>(
+        &mut self,
+        command: C,
+    ) -> Result<Reply, ESLError> {
+        self.rt.block_on(self.conn.send_recv(command))
+    }
+
+    /// Sends a command and waits at most `limit` for its reply.
+    pub fn send_recv_timeout<'a, C: Into<Command<'a>> + Debug>(
+        &mut self,
+        command: C,
+        limit: Duration,
+    ) -> Result<Reply, ESLError> {
+        self.rt
+            .block_on(self.conn.send_recv_timeout(command, limit))
+    }
+
+    /// Blocks until the next event arrives.
+    pub fn recv(&mut self) -> Result<Event, ESLError> {
+        self.rt.block_on(self.conn.recv())
+    }
+
+    /// Blocks at most `limit` for an event matching `matcher`,
+    /// see [`ESLConnection::wait_for`](crate::ESLConnection::wait_for).
+    pub fn wait_for(&mut self, matcher: &EventMatcher, limit: Duration) -> Result<Event, ESLError> {
+        self.rt.block_on(self.conn.wait_for(matcher, limit))
+    }
+
+    pub fn subscribe(&mut self, events: &EventSubscription) -> Result<Reply, ESLError> {
+        self.rt.block_on(self.conn.subscribe(events))
+    }
+
+    pub fn add_filter(&mut self, filter: &Filter) -> Result<Reply, ESLError> {
+        self.rt.block_on(self.conn.add_filter(filter))
+    }
+
+    /// Sends `exit` and closes the connection.
+    pub fn disconnect(mut self) {
+        self.rt.block_on(self.conn.disconnect())
+    }
+
+    /// The underlying async connection, ie. to use methods without a
+    /// blocking counterpart via [`block_on`](Self::block_on).
+    pub fn get_mut(&mut self) -> &mut crate::Inbound<T> {
+        &mut self.conn
+    }
+
+    /// Runs `f` against the async connection on the internal runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use eslrs::{ShutdownOptions, blocking::Inbound};
+    ///
+    /// let mut conn = Inbound::connect("0.0.0.0:8021", "ClueCon").unwrap();
+    /// let report = conn.block_on(|c| c.shutdown(ShutdownOptions::default()));
+    /// ```
+    pub fn block_on<'a, F, R>(&'a mut self, f: impl FnOnce(&'a mut crate::Inbound<T>) -> F) -> R
+    where
+        F: Future<Output = R>,
+    {
+        self.rt.block_on(f(&mut self.conn))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        EventName,
+        testing::{MockEvent, MockFreeSwitch},
+    };
+
+    #[test]
+    fn test_blocking_inbound() {
+        let server = Runtime::new().unwrap();
+        let mock = server
+            .block_on(MockFreeSwitch::new().api("status", "UP\n").listen())
+            .unwrap();
+
+        let mut conn = Inbound::connect(mock.addr().unwrap(), "ClueCon").unwrap();
+        let reply = conn.send_recv(Command::api("status")).unwrap();
+        assert_eq!(reply.get_body().unwrap().as_ref(), b"UP\n");
+
+        conn.subscribe(&EventSubscription::all()).unwrap();
+        mock.emit(MockEvent::new(EventName::Heartbeat));
+        let heartbeat = EventMatcher::new().event_name(EventName::Heartbeat);
+        conn.wait_for(&heartbeat, Duration::from_secs(1)).unwrap();
+
+        conn.disconnect();
+        assert_eq!(mock.commands().last().unwrap().name, "exit");
+    }
+}
@@ -0,0 +1,330 @@
+//! Framing for the event socket protocol.
+//!
+//! [`ESLCodec`] decodes the byte stream sent by FreeSWITCH into complete
+//! [`Event`]s (headers merged with any `Content-Length` body) and encodes
+//! [`Command`]s. It works on plain buffers, and with the `tokio` feature
+//! implements [`Decoder`](tokio_util::codec::Decoder) and
+//! [`Encoder`](tokio_util::codec::Encoder) for use with
+//! [`Framed`](tokio_util::codec::Framed) on any transport, ie. for proxies,
+//! recorders or traffic inspectors.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # #[cfg(feature = "tokio")]
+//! use eslrs::codec::ESLCodec;
+//! # #[cfg(feature = "tokio")]
+//! use futures_util::StreamExt;
+//! # #[cfg(feature = "tokio")]
+//! use tokio::net::TcpStream;
+//! # #[cfg(feature = "tokio")]
+//! use tokio_util::codec::FramedRead;
+//!
+//! # #[cfg(feature = "tokio")]
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let stream = TcpStream::connect("0.0.0.0:8021").await.unwrap();
+//! let mut frames = FramedRead::new(stream, ESLCodec::new());
+//! while let Some(Ok(frame)) = frames.next().await {
+//!     println!("{:?}: {:?}", frame.get_content_type(), frame.get_body());
+//! }
+//! # }
+//! # #[cfg(not(feature = "tokio"))]
+//! # fn main() {}
+//! ```
+use bytes::BytesMut;
+use std::mem;
+
+use crate::{
+    Command, ESLError,
+    error::ErrorKind,
+    event::{Event, HeaderMap, RawEvent},
+};
+
+const END: &[u8] = b"\r\n\r\n";
+
+/// Bounds on what a peer may send, exceeding them fails decoding
+/// with [`ErrorKind::ProtocolViolation`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CodecLimits {
+    /// Maximum number of headers in a single frame
+    pub max_headers: usize,
+    /// Maximum length of a single header line in bytes
+    pub max_line_length: usize,
+    /// Maximum `Content-Length` of a frame body in bytes
+    pub max_body_size: usize,
+}
+
+impl Default for CodecLimits {
+    fn default() -> Self {
+        Self {
+            // outbound `connect` replies carry every channel variable as a header
+            max_headers: 4096,
+            max_line_length: 64 * 1024,
+            max_body_size: 64 * 1024 * 1024,
+        }
+    }
+}
+
+fn violation(reason: &'static str) -> ESLError {
+    ESLError::new(ErrorKind::ProtocolViolation(reason))
+}
+
+/// Decodes [`Event`]s and encodes [`Command`]s.
+pub struct ESLCodec {
+    decoder: ESLDecoder,
+    limits: CodecLimits,
+}
+
+impl ESLCodec {
+    pub fn new() -> Self {
+        Self::with_limits(CodecLimits::default())
+    }
+
+    pub fn with_limits(limits: CodecLimits) -> Self {
+        Self {
+            decoder: ESLDecoder::new(),
+            limits,
+        }
+    }
+}
+
+impl Default for ESLCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ESLCodec {
+    /// Writes `item` to `dst`.
+    pub fn encode(&mut self, item: Command<'_>, dst: &mut BytesMut) -> Result<(), ESLError> {
+        // Content-Length header of a body, plus its blank line
+        let body_len = item.body.as_ref().map_or(0, |b| b.len() + 40);
+        let len = item.cmd.len() + item.args.len() + body_len + END.len();
+        dst.reserve(len);
+        dst.extend_from_slice(item.cmd.as_bytes());
+        dst.extend_from_slice(item.args.as_bytes());
+        if let Some(body) = &item.body {
+            if !item.args.ends_with('\n') {
+                dst.extend_from_slice(b"\n");
+            }
+            dst.extend_from_slice(format!("Content-Length: {}\n\n", body.len()).as_bytes());
+            dst.extend_from_slice(body);
+        }
+        dst.extend_from_slice(END);
+        Ok(())
+    }
+}
+
+enum ESLDecoder {
+    /// Scanning for the blank line that ends the header block
+    PendingHeader {
+        line_start: usize,
+        current: usize,
+        count: usize,
+    },
+    PendingBody {
+        headers: HeaderMap,
+        len: usize,
+    },
+}
+
+impl ESLDecoder {
+    fn new() -> Self {
+        ESLDecoder::PendingHeader {
+            line_start: 0,
+            current: 0,
+            count: 0,
+        }
+    }
+}
+
+impl ESLCodec {
+    /// Decodes the next frame of `src`, `None` until one is complete.
+    pub fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Event>, ESLError> {
+        loop {
+            match &mut self.decoder {
+                ESLDecoder::PendingHeader {
+                    line_start,
+                    current,
+                    count,
+                } => {
+                    let Some(n) = src[*current..].iter().position(|b| *b == b'\n') else {
+                        if src.len() - *line_start > self.limits.max_line_length {
+                            return Err(violation("header line too long"));
+                        }
+                        *current = src.len();
+                        return Ok(None);
+                    };
+
+                    // Position of newline in the full buffer
+                    let newline_pos = *current + n;
+                    if newline_pos - *line_start > self.limits.max_line_length {
+                        return Err(violation("header line too long"));
+                    }
+
+                    if newline_pos > *line_start {
+                        if *count >= self.limits.max_headers {
+                            return Err(violation("too many headers"));
+                        }
+                        *count += 1;
+                        *line_start = newline_pos + 1;
+                        *current = *line_start;
+                        continue;
+                    }
+
+                    // Empty line signals end of headers, keep the block
+                    // whole and let `HeaderMap` index into it
+                    let block = src.split_to(newline_pos + 1).freeze();
+                    let headers = HeaderMap::parse(block.slice(..newline_pos));
+                    self.decoder = ESLDecoder::new();
+
+                    let len = headers
+                        .get_header("Content-Length")
+                        .map(|v| v.parse::<usize>())
+                        .transpose()
+                        .map_err(|_| violation("invalid Content-Length"))?;
+                    match len {
+                        Some(len) if len > self.limits.max_body_size => {
+                            return Err(violation("body too large"));
+                        }
+                        Some(len) => self.decoder = ESLDecoder::PendingBody { headers, len },
+                        None => return Ok(Some(RawEvent::new(headers, None).into())),
+                    }
+                }
+                ESLDecoder::PendingBody { len, .. } => {
+                    if src.len() < *len {
+                        return Ok(None);
+                    }
+                    let body = src.split_to(*len).freeze();
+                    let ESLDecoder::PendingBody { headers, .. } =
+                        mem::replace(&mut self.decoder, ESLDecoder::new())
+                    else {
+                        unreachable!()
+                    };
+                    return Ok(Some(RawEvent::new(headers, Some(body)).into()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<'a> tokio_util::codec::Encoder<Command<'a>> for ESLCodec {
+    type Error = ESLError;
+    fn encode(&mut self, item: Command<'a>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        ESLCodec::encode(self, item, dst)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio_util::codec::Decoder for ESLCodec {
+    type Item = Event;
+    type Error = ESLError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        ESLCodec::decode(self, src)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_headers_only() {
+        let mut codec = ESLCodec::new();
+        let mut src = BytesMut::from(&b"Content-Type: auth/request\n\nContent-Type: comm"[..]);
+        let frame = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(frame.get_content_type(), Some("auth/request"));
+        assert!(frame.get_body().is_none());
+        assert!(codec.decode(&mut src).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_decode_partial_body() {
+        let mut codec = ESLCodec::new();
+        let mut src =
+            BytesMut::from(&b"Content-Type: api/response\nContent-Length: 12\n\n+OK "[..]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+
+        src.extend_from_slice(b"accepted");
+        let frame = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(frame.get_content_type(), Some("api/response"));
+        assert_eq!(frame.get_body().unwrap().as_ref(), b"+OK accepted");
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn test_decode_content_length_exact_match() {
+        let mut codec = ESLCodec::new();
+        let mut src =
+            BytesMut::from(&b"Content-Length-Extra: 3\ncontent-length: 12\n\nHello World!"[..]);
+        let frame = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(frame.get_header("Content"), None);
+        assert_eq!(frame.get_body().unwrap().as_ref(), b"Hello World!");
+    }
+
+    fn limited() -> ESLCodec {
+        ESLCodec::with_limits(CodecLimits {
+            max_headers: 2,
+            max_line_length: 32,
+            max_body_size: 8,
+        })
+    }
+
+    fn assert_violation(res: Result<Option<Event>, ESLError>) {
+        match res {
+            Err(e) => assert!(matches!(e.kind(), ErrorKind::ProtocolViolation(_))),
+            Ok(_) => panic!("expected decode error"),
+        }
+    }
+
+    #[test]
+    fn test_decode_too_many_headers() {
+        let mut src = BytesMut::from(&b"A: 1\nB: 2\nC: 3\n\n"[..]);
+        assert_violation(limited().decode(&mut src));
+    }
+
+    #[test]
+    fn test_decode_line_too_long() {
+        // Rejected before the newline ever arrives
+        let mut src = BytesMut::from(&[b'a'; 64][..]);
+        assert_violation(limited().decode(&mut src));
+
+        let mut src = BytesMut::from(&[b"Header: ".as_ref(), &[b'a'; 40], b"\n"].concat()[..]);
+        assert_violation(limited().decode(&mut src));
+    }
+
+    #[test]
+    fn test_decode_body_too_large() {
+        let mut src = BytesMut::from(&b"Content-Length: 1000000\n\n"[..]);
+        assert_violation(limited().decode(&mut src));
+    }
+
+    #[test]
+    fn test_decode_invalid_content_length() {
+        let mut src = BytesMut::from(&b"Content-Length: abc\n\nxyz"[..]);
+        assert_violation(ESLCodec::new().decode(&mut src));
+    }
+
+    #[test]
+    fn test_encode_command() {
+        let mut codec = ESLCodec::new();
+        let mut dst = BytesMut::new();
+        codec.encode(Command::api("status"), &mut dst).unwrap();
+        assert_eq!(dst.as_ref(), b"api status\r\n\r\n");
+    }
+
+    #[test]
+    fn test_encode_command_body() {
+        let mut codec = ESLCodec::new();
+        let mut dst = BytesMut::new();
+        let cmd = Command::sendevent("NOTIFY\nprofile: internal").body(&b"\x00\xff"[..]);
+        codec.encode(cmd, &mut dst).unwrap();
+        assert_eq!(
+            dst.as_ref(),
+            b"sendevent NOTIFY\nprofile: internal\nContent-Length: 2\n\n\x00\xff\r\n\r\n"
+        );
+    }
+}
@@ -1,10 +1,12 @@
-use crate::EventBuilder;
+use crate::Header;
+use bytes::Bytes;
 use std::{borrow::Cow, fmt::Display};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Command<'a> {
     pub(crate) cmd: &'static str,
     pub(crate) args: Cow<'a, str>,
+    pub(crate) body: Option<Bytes>,
 }
 
 impl<'a> Command<'a> {
@@ -14,9 +16,34 @@ impl<'a> Command<'a> {
         let res: Command<'static> = Command {
             cmd: self.cmd,
             args: s.into(),
+            body: self.body.clone(),
         };
         res
     }
+
+    /// Attaches a body, sent verbatim after the headers with its `Content-Length`.
+    ///
+    /// Bodies don't need to be UTF-8, ie. binary payloads of `SEND_MESSAGE`
+    /// or `NOTIFY` events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eslrs::Command;
+    ///
+    /// let notify = Command::sendevent(
+    ///     "NOTIFY\nprofile: internal\ncontent-type: application/octet-stream\n",
+    /// )
+    /// .body(vec![0x00, 0xff, 0x10]);
+    /// ```
+    pub fn body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    pub fn get_body(&self) -> Option<&Bytes> {
+        self.body.as_ref()
+    }
 }
 
 impl<'a> From<&'a str> for Command<'a> {
@@ -24,6 +51,7 @@ impl<'a> From<&'a str> for Command<'a> {
         Command {
             cmd: "",
             args: value.into(),
+            body: None,
         }
     }
 }
@@ -33,6 +61,7 @@ impl<'a> From<String> for Command<'a> {
         Command {
             cmd: "",
             args: value.into(),
+            body: None,
         }
     }
 }
@@ -45,6 +74,7 @@ where
         Command {
             cmd: "",
             args: value.as_ref().into(),
+            body: None,
         }
     }
 }
@@ -60,6 +90,7 @@ macro_rules! create_command {
                 Command {
                     cmd: concat!($cmd, " "),
                     args: s.into(),
+                    body: None,
                 }
             }
         }
@@ -71,6 +102,7 @@ macro_rules! create_command {
                 Command {
                     cmd: concat!($cmd, " "),
                     args: "".into(),
+                    body: None,
                 }
             }
         }
@@ -126,6 +158,10 @@ create_command!(
     /// ```
     filter_delete, "filter delete");
 
+create_command!(
+    /// Removes every event filter.
+    filter_delete_all, "filter delete all", no_args);
+
 create_command!(
     /// Subscribes to events in Plain format.
     ///
@@ -150,7 +186,8 @@ create_command!(
 create_command!(
     /// Sends a custom event to FreeSWITCH.
     ///
-    /// Use with the `EventBuilder!` macro to construct event data.
+    /// Use [`OutgoingEvent`](crate::event::OutgoingEvent) or the
+    /// `EventBuilder!` macro to construct event data.
     ///
     /// # Examples
     ///
@@ -186,15 +223,48 @@ create_command!(
     /// // Subscribe to specific events
     /// Command::events_json("CHANNEL_CREATE CHANNEL_DESTROY");
     /// ```
+    ///
+    /// There is no `event xml` counterpart, XML events aren't supported.
     events_json, "event json");
 
-// TODO: impl!
-//create_command!(events_xml, "event xml");
+create_command!(
+    /// Unsubscribes from events previously subscribed to.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - Space-separated event names, `CUSTOM` followed by subclasses
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eslrs::Command;
+    /// Command::nixevent("CHANNEL_CREATE CUSTOM sofia::register");
+    /// ```
+    nixevent);
 
 create_command!(
     /// Disables all event subscriptions.
     events_disable, "noevents", no_args);
 
+create_command!(
+    /// Receives FreeSWITCH log lines as `log/data` events.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - lowest level to receive, by name or number, ie. `debug` or `7`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eslrs::Command;
+    /// Command::log("info");
+    /// ```
+    log);
+
+create_command!(
+    /// Stops receiving log lines.
+    nolog, "nolog", no_args);
+
 create_command!(
     /// Disconnects from FreeSWITCH.
     disconnect, "exit", no_args);
@@ -229,8 +299,8 @@ where
         if self._loop > 1 {
             writeln!(f, "loop: {}", self._loop)?
         }
-        if self.event_id.is_some() {
-            writeln!(f, "Job-UUID: {}", self.event_id.as_ref().unwrap())?;
+        if let Some(event_id) = &self.event_id {
+            writeln!(f, "Job-UUID: {}", event_id)?;
         }
         Ok(())
     }
@@ -265,6 +335,7 @@ impl<'a> Command<'a> {
         Command {
             cmd: "bgapi ",
             args: format!("{}\nJob-UUID: {}\n", s, event_id).into(),
+            body: None,
         }
     }
     pub fn execute<T1, T2, T3>(uuid: T1, app_name: T2, args: T3) -> Command<'a>
@@ -314,20 +385,23 @@ impl<'a> Command<'a> {
         T3: Into<std::borrow::Cow<'a, str>>,
         T4: Display,
     {
-        let body = args.into();
+        let body: Cow<'a, str> = args.into();
         let uuid: Cow<'a, str> = uuid.into();
         let app_name: Cow<'a, str> = app_name.into();
 
-        let event = EventBuilder!(uuid,
-            "execute-app-name" => app_name,
-            "call-command" => "execute",
-            => config;
-            body
+        let headers = format!(
+            "{uuid}\n{}{config}",
+            Header!(
+                "execute-app-name" => app_name,
+                "call-command" => "execute",
+                "Content-Type" => "text/plain"
+            ),
         );
 
         Command {
             cmd: "sendmsg ",
-            args: event.into(),
+            args: headers.into(),
+            body: Some(Bytes::from(body.into_owned())),
         }
     }
 }
@@ -1,49 +1,162 @@
 use crate::event::RawEvent;
 use crate::{
-    Command, ESLError,
+    Command, ESLConfig, ESLError, EventMatcher, EventSubscription, Filter, Keepalive,
+    codec::{CodecLimits, ESLCodec},
+    error::ErrorKind,
     event::{Event, Reply},
+    rt::{self, Driver, Runtime, Transport},
 };
+use bytes::{Buf, BytesMut};
 use futures_util::stream::Fuse;
-use futures_util::{Sink, SinkExt, StreamExt, ready};
-use pin_project_lite::pin_project;
+use futures_util::{Sink, SinkExt, Stream, StreamExt, ready};
 use std::fmt::Debug;
-use std::time::Duration;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
 use std::{
     collections::VecDeque,
-    mem::{self},
-    task::{Poll, Waker},
+    io,
+    task::{Context, Poll, Waker},
 };
 
 #[cfg(feature = "tracing")]
 use tracing::{instrument, warn};
 
-use tokio::io::{AsyncRead, AsyncWrite};
-use tokio_stream::Stream;
-use tokio_util::{
-    bytes::Bytes,
-    codec::{Decoder, Encoder, Framed},
-};
+// Read size, large enough for most frames in one go
+const READ_SIZE: usize = 8 * 1024;
 
 pub struct ESLConnection<S> {
     inner: Fuse<ESLConnInner<S>>,
+    keepalive: Option<Keepalive>,
+    command_timeout: Option<Duration>,
+    disconnect_timeout: Duration,
+    timed_out: bool,
+    shutdown: bool,
+    subscription: EventSubscription,
+    filters: Vec<Filter>,
+}
+
+/// What to do with events still arriving during [`ESLConnection::shutdown`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Drain {
+    /// Return drained events in [`ShutdownReport::events`]
+    #[default]
+    Collect,
+    /// Keep drained events queued for [`ESLConnection::recv`]
+    Keep,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownOptions {
+    /// Overall limit for the shutdown, defaults to [`ESLConfig::disconnect_timeout`]
+    pub timeout: Option<Duration>,
+    pub drain: Drain,
+}
+
+/// Outcome of [`ESLConnection::shutdown`].
+#[derive(Debug)]
+pub struct ShutdownReport {
+    /// Reply to `exit`, if the peer acknowledged it
+    pub exit_reply: Option<Reply>,
+    /// Events drained with [`Drain::Collect`], including any that were already queued
+    pub events: Vec<Event>,
+    /// `Ok` if the peer closed the socket within the timeout,
+    /// otherwise the error that interrupted the shutdown
+    pub result: Result<(), ESLError>,
 }
 
 impl<S> ESLConnection<S>
 where
-    S: AsyncRead + AsyncWrite + Unpin,
+    S: Unpin,
 {
-    pub fn new(stream: S) -> Self {
+    /// Creates a connection over `stream`, driven by the [`Runtime`] whose IO
+    /// traits the stream implements, see [`rt`](crate::rt).
+    pub fn new<R: Runtime>(stream: S) -> Self
+    where
+        S: Transport<R>,
+    {
+        Self::with_config(stream, &ESLConfig::default())
+    }
+
+    /// Creates a connection applying the connection level settings of `config`,
+    /// ie. [`ESLConfig::keepalive`] and [`ESLConfig::command_timeout`].
+    pub fn with_config<R: Runtime>(stream: S, config: &ESLConfig) -> Self
+    where
+        S: Transport<R>,
+    {
         Self {
-            inner: ESLConnInner::new(stream).fuse(),
+            inner: ESLConnInner::new(stream, Driver::new::<R>(), config.limits).fuse(),
+            keepalive: config.keepalive,
+            command_timeout: config.command_timeout,
+            disconnect_timeout: config.disconnect_timeout,
+            timed_out: false,
+            shutdown: false,
+            subscription: EventSubscription::new(),
+            filters: vec![],
         }
     }
 
+    /// Sends a command and waits for its reply.
+    ///
+    /// Limited by [`ESLConfig::command_timeout`] if set. It is safe to drop
+    /// the returned future early, the late reply is discarded
+    /// rather than being handed to the next command.
     #[cfg_attr(feature = "tracing", instrument(skip(self), ret, err))]
     pub async fn send_recv<'a, T: Into<Command<'a>> + Debug>(
         &mut self,
         command: T,
     ) -> Result<Reply, ESLError> {
-        self.inner.send(command.into()).await?;
+        match self.command_timeout {
+            Some(limit) => self.send_recv_timeout(command, limit).await,
+            None => self.send_recv_inner(command.into()).await,
+        }
+    }
+
+    /// Sends a command and waits at most `limit` for its reply.
+    ///
+    /// Fails with [`ErrorKind::Timeout`] if no reply arrived in time,
+    /// the connection remains usable afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "tokio")]
+    /// use eslrs::{Command, ErrorKind, Inbound};
+    /// use std::time::Duration;
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let mut conn = Inbound::connect("0.0.0.0:8021", "ClueCon").await.unwrap();
+    /// match conn.send_recv_timeout(Command::api("status"), Duration::from_secs(2)).await {
+    ///     Err(e) if e.kind() == &ErrorKind::Timeout => println!("api call is stuck"),
+    ///     res => println!("{:?}", res),
+    /// }
+    /// # }
+    /// # #[cfg(not(feature = "tokio"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg_attr(feature = "tracing", instrument(skip(self), ret, err))]
+    pub async fn send_recv_timeout<'a, T: Into<Command<'a>> + Debug>(
+        &mut self,
+        command: T,
+        limit: Duration,
+    ) -> Result<Reply, ESLError> {
+        let driver = self.inner.get_ref().driver;
+        driver
+            .timeout(limit, self.send_recv_inner(command.into()))
+            .await
+            .unwrap_or_else(|| Err(ESLError::new(ErrorKind::Timeout)))
+    }
+
+    async fn send_recv_inner(&mut self, command: Command<'_>) -> Result<Reply, ESLError> {
+        if self.timed_out || self.shutdown {
+            return Err(ESLError::new(ErrorKind::ConnectionClosed));
+        }
+        self.exchange(command).await
+    }
+
+    async fn exchange(&mut self, command: Command<'_>) -> Result<Reply, ESLError> {
+        self.inner.send(command).await?;
         if let Some(event) = self.inner.get_mut().pop_reply() {
             Ok(event.try_into()?)
         } else {
@@ -55,24 +168,337 @@ where
 
     #[cfg_attr(feature = "tracing", instrument(skip(self), ret, err))]
     pub async fn recv(&mut self) -> Result<Event, ESLError> {
-        if let Some(e) = self.inner.next().await {
-            Ok(Event::from(e))
-        } else {
-            Err(ESLError::new(crate::error::ErrorKind::ConnectionClosed))
+        self.recv_matching(|_| true).await
+    }
+
+    /// Receives the first event satisfying `predicate`.
+    ///
+    /// Events that don't match stay queued, in order, for later calls to
+    /// [`recv`](Self::recv) or `recv_matching`. It is safe to drop the
+    /// returned future early, no events are lost.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "tokio")]
+    /// use eslrs::{EventSubscription, Inbound};
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let mut conn = Inbound::connect("0.0.0.0:8021", "ClueCon").await.unwrap();
+    /// conn.subscribe(&EventSubscription::all()).await.unwrap();
+    /// let plain = conn.recv_matching(|e| e.is_plain_event()).await.unwrap();
+    /// # }
+    /// # #[cfg(not(feature = "tokio"))]
+    /// # fn main() {}
+    /// ```
+    pub async fn recv_matching<F>(&mut self, mut predicate: F) -> Result<Event, ESLError>
+    where
+        F: FnMut(&Event) -> bool,
+    {
+        if self.timed_out {
+            return Err(ESLError::new(ErrorKind::ConnectionClosed));
+        }
+        let Some(keepalive) = self.keepalive.filter(|_| !self.shutdown) else {
+            return self.recv_event(&mut predicate).await;
+        };
+        let driver = self.inner.get_ref().driver;
+        let mut sleep = driver.sleep_until(self.inner.get_ref().last_activity + keepalive.interval);
+        loop {
+            let idle = self.inner.get_ref().last_activity + keepalive.interval;
+            sleep.as_mut().reset(idle);
+            match rt::timeout(sleep.as_mut(), self.recv_event(&mut predicate)).await {
+                Some(e) => return e,
+                None => {
+                    if self.inner.get_ref().last_activity + keepalive.interval > (driver.now)() {
+                        continue;
+                    }
+                    self.probe(keepalive.timeout).await?;
+                }
+            }
         }
     }
 
+    /// Waits at most `limit` for an event matching `matcher`, see
+    /// [`recv_matching`](Self::recv_matching).
+    ///
+    /// Fails with [`ErrorKind::Timeout`] if none arrived in time, the
+    /// connection remains usable and every event received stays queued.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "tokio")]
+    /// use eslrs::{Command, EventMatcher, EventName, EventSubscription, Inbound};
+    /// use std::time::Duration;
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let mut conn = Inbound::connect("0.0.0.0:8021", "ClueCon").await.unwrap();
+    /// let uuid = "7f4db78a-17d7-11dd-b7a0-db4edd065621";
+    /// conn.subscribe(&EventSubscription::all()).await.unwrap();
+    /// conn.send_recv(Command::api(format!("uuid_answer {uuid}"))).await.unwrap();
+    ///
+    /// let answered = EventMatcher::new()
+    ///     .event_name(EventName::ChannelAnswer)
+    ///     .unique_id(uuid);
+    /// let event = conn.wait_for(&answered, Duration::from_secs(5)).await.unwrap();
+    /// # }
+    /// # #[cfg(not(feature = "tokio"))]
+    /// # fn main() {}
+    /// ```
+    pub async fn wait_for(
+        &mut self,
+        matcher: &EventMatcher,
+        limit: Duration,
+    ) -> Result<Event, ESLError> {
+        let driver = self.inner.get_ref().driver;
+        driver
+            .timeout(limit, self.recv_matching(|e| matcher.matches(e)))
+            .await
+            .unwrap_or_else(|| Err(ESLError::new(ErrorKind::Timeout)))
+    }
+
+    async fn recv_event<F>(&mut self, predicate: &mut F) -> Result<Event, ESLError>
+    where
+        F: FnMut(&Event) -> bool,
+    {
+        // Only frames queued since the last poll need checking
+        let mut checked = 0;
+        std::future::poll_fn(|cx| {
+            let inner = self.inner.get_mut();
+            loop {
+                let queue = &mut inner.read_queue;
+                if let Some(i) = queue.iter().skip(checked).position(&mut *predicate) {
+                    return Poll::Ready(Ok(queue.remove(checked + i).expect("position in queue")));
+                }
+                checked = queue.len();
+                match ready!(Pin::new(&mut *inner).poll_inner_stream(cx)) {
+                    Ok(Some(())) => continue,
+                    Ok(None) => {
+                        return Poll::Ready(Err(ESLError::new(ErrorKind::ConnectionClosed)));
+                    }
+                    Err(e) => return Poll::Ready(Err(e)),
+                }
+            }
+        })
+        .await
+    }
+
+    // Peer has been silent, check it's still there
+    async fn probe(&mut self, limit: Duration) -> Result<(), ESLError> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!("connection idle, sending keepalive probe");
+        let res = self.send_recv_timeout(Command::api("status"), limit).await;
+        if let Err(e) = &res
+            && e.kind() == &ErrorKind::Timeout
+        {
+            #[cfg(feature = "tracing")]
+            warn!("keepalive probe timed out, peer is unresponsive");
+            self.timed_out = true;
+        }
+        res.map(|_| ())
+    }
+
+    /// Sends `exit` and closes the connection, see [`ESLConnection::shutdown`].
+    ///
+    /// Events still in flight remain available from [`ESLConnection::recv`].
     pub async fn disconnect(&mut self) {
-        let _ = tokio::time::timeout(
-            Duration::from_secs(5),
-            self.send_recv(Command::disconnect()),
-        )
-        .await;
-        let _ = self.inner.close().await;
+        let options = ShutdownOptions {
+            drain: Drain::Keep,
+            ..Default::default()
+        };
+        let _ = self.shutdown(options).await;
+    }
+
+    /// Gracefully closes the connection.
+    ///
+    /// New commands are refused straight away, then `exit` is sent once replies to any
+    /// in-flight commands have arrived. Remaining events are drained until the peer
+    /// closes the socket, or the timeout elapses.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "tokio")]
+    /// use eslrs::{Inbound, ShutdownOptions};
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let mut conn = Inbound::connect("0.0.0.0:8021", "ClueCon").await.unwrap();
+    /// let report = conn.shutdown(ShutdownOptions::default()).await;
+    /// for event in report.events {
+    ///     println!("late event: {:?}", event.get_header("Event-Name"));
+    /// }
+    /// if let Err(e) = report.result {
+    ///     println!("unclean shutdown: {e}");
+    /// }
+    /// # }
+    /// # #[cfg(not(feature = "tokio"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg_attr(feature = "tracing", instrument(skip(self)))]
+    pub async fn shutdown(&mut self, options: ShutdownOptions) -> ShutdownReport {
+        self.shutdown = true;
+        let limit = options.timeout.unwrap_or(self.disconnect_timeout);
+        let mut exit_reply = None;
+        let driver = self.inner.get_ref().driver;
+
+        let result = driver
+            .timeout(limit, async {
+                exit_reply = Some(self.exchange(Command::disconnect()).await?);
+                std::future::poll_fn(|cx| Pin::new(self.inner.get_mut()).poll_drain(cx)).await?;
+                let _ = self.inner.close().await;
+                Ok(())
+            })
+            .await
+            .unwrap_or_else(|| Err(ESLError::new(ErrorKind::Timeout)));
+
+        let events = match options.drain {
+            Drain::Collect => self.inner.get_mut().read_queue.drain(..).collect(),
+            Drain::Keep => vec![],
+        };
+
+        #[cfg(feature = "tracing")]
+        if let Err(e) = &result {
+            warn!(error = %e, "unclean shutdown");
+        }
+
+        ShutdownReport {
+            exit_reply,
+            events,
+            result,
+        }
     }
 
     pub fn is_disconnected(&self) -> bool {
-        self.inner.is_done()
+        self.timed_out || self.shutdown || self.inner.is_done() || self.inner.get_ref().closed
+    }
+
+    /// Subscribes to `events`, adding to the current subscription.
+    ///
+    /// The events are tracked by [`subscription`](Self::subscription) once
+    /// FreeSWITCH accepts them, subscriptions sent as raw commands via
+    /// [`send_recv`](Self::send_recv) are not.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "tokio")]
+    /// use eslrs::{EventName::*, EventSubscription, Inbound};
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let mut conn = Inbound::connect("0.0.0.0:8021", "ClueCon").await.unwrap();
+    /// let sub = EventSubscription::new().events([ChannelCreate, ChannelHangup]);
+    /// conn.subscribe(&sub).await.unwrap();
+    ///
+    /// // Replay the same subscription on a new connection
+    /// let mut other = Inbound::connect("0.0.0.0:8021", "ClueCon").await.unwrap();
+    /// other.subscribe(conn.subscription()).await.unwrap();
+    /// # }
+    /// # #[cfg(not(feature = "tokio"))]
+    /// # fn main() {}
+    /// ```
+    pub async fn subscribe(&mut self, events: &EventSubscription) -> Result<Reply, ESLError> {
+        let reply = self.send_recv(events.command()).await?;
+        if reply.is_ok() {
+            self.subscription.add(events);
+        }
+        Ok(reply)
+    }
+
+    /// Unsubscribes from `events` with `nixevent`, the format is ignored.
+    pub async fn unsubscribe(&mut self, events: &EventSubscription) -> Result<Reply, ESLError> {
+        let reply = self.send_recv(events.nix_command()).await?;
+        if reply.is_ok() {
+            self.subscription.remove(events);
+        }
+        Ok(reply)
+    }
+
+    /// Unsubscribes from all events with `noevents`.
+    pub async fn unsubscribe_all(&mut self) -> Result<Reply, ESLError> {
+        let reply = self.send_recv(Command::events_disable()).await?;
+        if reply.is_ok() {
+            self.subscription = EventSubscription::new().format(self.subscription.get_format());
+        }
+        Ok(reply)
+    }
+
+    /// Events currently subscribed to via [`subscribe`](Self::subscribe).
+    pub fn subscription(&self) -> &EventSubscription {
+        &self.subscription
+    }
+
+    /// Adds a server side event filter.
+    ///
+    /// Tracked by [`filters`](Self::filters) once FreeSWITCH accepts it,
+    /// filters sent as raw commands via [`send_recv`](Self::send_recv) are not.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "tokio")]
+    /// use eslrs::{EventName, EventSubscription, Filter, Inbound};
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let mut conn = Inbound::connect("0.0.0.0:8021", "ClueCon").await.unwrap();
+    /// conn.subscribe(&EventSubscription::all()).await.unwrap();
+    /// conn.add_filter(&Filter::event_name(EventName::Heartbeat)).await.unwrap();
+    /// # }
+    /// # #[cfg(not(feature = "tokio"))]
+    /// # fn main() {}
+    /// ```
+    pub async fn add_filter(&mut self, filter: &Filter) -> Result<Reply, ESLError> {
+        let reply = self.send_recv(filter.command()).await?;
+        if reply.is_ok() && !self.filters.contains(filter) {
+            self.filters.push(filter.clone());
+        }
+        Ok(reply)
+    }
+
+    /// Only receive events of the channel `uuid`, in addition to any other filters.
+    pub async fn filter_unique_id(&mut self, uuid: impl Into<String>) -> Result<Reply, ESLError> {
+        self.add_filter(&Filter::unique_id(uuid)).await
+    }
+
+    /// Removes a filter previously added.
+    pub async fn delete_filter(&mut self, filter: &Filter) -> Result<Reply, ESLError> {
+        let reply = self.send_recv(filter.delete_command()).await?;
+        if reply.is_ok() {
+            self.filters.retain(|f| f != filter);
+        }
+        Ok(reply)
+    }
+
+    /// Removes every filter with `filter delete all`.
+    pub async fn delete_all_filters(&mut self) -> Result<Reply, ESLError> {
+        let reply = self.send_recv(Command::filter_delete_all()).await?;
+        if reply.is_ok() {
+            self.filters.clear();
+        }
+        Ok(reply)
+    }
+
+    /// Filters currently active via [`add_filter`](Self::add_filter).
+    pub fn filters(&self) -> &[Filter] {
+        &self.filters
+    }
+
+    /// Returns a reference to the underlying stream.
+    pub fn get_ref(&self) -> &S {
+        &self.inner.get_ref().stream
+    }
+
+    pub(crate) fn driver(&self) -> Driver<S> {
+        self.inner.get_ref().driver
     }
 }
 
@@ -82,33 +508,44 @@ enum SendRecvState {
     Complete(RawEvent),
 }
 
-pin_project! {
-    struct ESLConnInner<S> {
-        #[pin]
-        stream: Framed<S, ESLCodec>,
-        active_write: Option<SendRecvState>,
-        pending_read: Option<RawHeaders>,
-        read_queue: VecDeque<RawEvent>,
-    }
+struct ESLConnInner<S> {
+    stream: S,
+    driver: Driver<S>,
+    codec: ESLCodec,
+    read_buf: BytesMut,
+    write_buf: BytesMut,
+    // stream reached EOF, remaining frames are still decoded
+    eof: bool,
+    active_write: Option<SendRecvState>,
+    read_queue: VecDeque<Event>,
+    // peer closed the stream or sent an undecodable frame
+    closed: bool,
+    last_activity: Instant,
+    // replies still owed to abandoned commands
+    discard: usize,
 }
 
-impl<S> ESLConnInner<S>
-where
-    S: tokio::io::AsyncRead + tokio::io::AsyncWrite,
-{
-    pub fn new(stream: S) -> Self {
+impl<S> ESLConnInner<S> {
+    fn new(stream: S, driver: Driver<S>, limits: CodecLimits) -> Self {
         Self {
-            stream: Framed::new(stream, ESLCodec::new()),
-            pending_read: None,
+            stream,
+            driver,
+            codec: ESLCodec::with_limits(limits),
+            read_buf: BytesMut::new(),
+            write_buf: BytesMut::new(),
+            eof: false,
             active_write: None,
             read_queue: VecDeque::new(),
+            closed: false,
+            last_activity: (driver.now)(),
+            discard: 0,
         }
     }
 }
 
 impl<S> ESLConnInner<S>
 where
-    S: AsyncWrite + AsyncRead + Unpin,
+    S: Unpin,
 {
     fn pop_reply(&mut self) -> Option<RawEvent> {
         match self.active_write.take() {
@@ -121,147 +558,202 @@ where
         }
     }
 
-    fn poll_inner_stream(
-        mut self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Result<Option<()>, ESLError>> {
+    // Read until the peer closes, queueing any events
+    fn poll_drain(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), ESLError>> {
+        while ready!(self.as_mut().poll_inner_stream(cx))?.is_some() {}
+        Poll::Ready(Ok(()))
+    }
+
+    // Next frame off the wire, nothing further once closed
+    fn poll_frame(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Event, ESLError>>> {
+        if self.closed {
+            return Poll::Ready(None);
+        }
         loop {
-            let e = match ready!(self.as_mut().project().stream.poll_next(cx)) {
-                None => return Poll::Ready(Ok(None)),
-                Some(Ok(ESLFrame::Header(h))) => {
-                    if h.get_header("Content-Length").is_none() {
-                        RawEvent::new(h, None)
-                    } else {
-                        if let Some(h) = self.pending_read.replace(h) {
-                            #[cfg(feature = "tracing")]
-                            warn!("recv'd new event whilst pending prev body");
-                            RawEvent::new(h, None)
-                        } else {
-                            continue;
-                        }
-                    }
+            if let Some(frame) = self.codec.decode(&mut self.read_buf).transpose() {
+                return Poll::Ready(Some(frame));
+            }
+            if self.eof {
+                if self.read_buf.is_empty() {
+                    return Poll::Ready(None);
+                }
+                let err = io::Error::other("bytes remaining on stream");
+                return Poll::Ready(Some(Err(err.into())));
+            }
+
+            let len = self.read_buf.len();
+            self.read_buf.resize(len + READ_SIZE, 0);
+            let res =
+                (self.driver.poll_read)(Pin::new(&mut self.stream), cx, &mut self.read_buf[len..]);
+            let n = match res {
+                Poll::Ready(Ok(n)) => n,
+                Poll::Ready(Err(e)) => {
+                    self.read_buf.truncate(len);
+                    return Poll::Ready(Some(Err(e.into())));
                 }
-                Some(Ok(ESLFrame::Body(b))) => {
-                    let h = self.pending_read.take().unwrap_or(RawHeaders::new());
-                    RawEvent::new(h, b)
+                Poll::Pending => {
+                    self.read_buf.truncate(len);
+                    return Poll::Pending;
                 }
-                Some(Err(e)) => return Poll::Ready(Err(e)),
             };
+            self.read_buf.truncate(len + n);
+            self.eof = n == 0;
+        }
+    }
 
-            if e.is_reply() || e.is_api_response() {
-                if self.active_write.is_some() {
-                    match self.active_write.replace(SendRecvState::Complete(e)) {
-                        Some(SendRecvState::Pending(w)) => w.wake(),
-                        _ => {}
-                    }
-                } else {
-                    // Currently we drop unexpected responses here
-                    // but it shouldn't happens since the sink interface
-                    // is only SendRecv
-                    #[cfg(feature = "tracing")]
-                    {
-                        let body = e.get_body().map(|b| {
-                            String::from_utf8(b.to_vec()).unwrap_or("parsing error".to_string())
-                        });
-                        warn!(body, "recv'd unexpected response/reply")
-                    }
+    // Writes out buffered commands
+    fn poll_write_buf(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), ESLError>> {
+        while !self.write_buf.is_empty() {
+            let n = ready!((self.driver.poll_write)(
+                Pin::new(&mut self.stream),
+                cx,
+                &self.write_buf
+            ))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::Error::from(io::ErrorKind::WriteZero).into()));
+            }
+            self.write_buf.advance(n);
+        }
+        ready!((self.driver.poll_flush)(Pin::new(&mut self.stream), cx))?;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_inner_stream(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<()>, ESLError>> {
+        let frame = ready!(self.poll_frame(cx));
+        self.last_activity = (self.driver.now)();
+        let e = match frame {
+            None => {
+                self.closed = true;
+                return Poll::Ready(Ok(None));
+            }
+            Some(Ok(event)) => event.0,
+            Some(Err(e)) => {
+                self.closed = true;
+                return Poll::Ready(Err(e));
+            }
+        };
+
+        if e.is_reply() || e.is_api_response() {
+            if self.discard > 0 {
+                self.discard -= 1;
+                #[cfg(feature = "tracing")]
+                tracing::debug!("discarding reply of abandoned command");
+            } else if self.active_write.is_some() {
+                if let Some(SendRecvState::Pending(w)) =
+                    self.active_write.replace(SendRecvState::Complete(e))
+                {
+                    w.wake()
                 }
             } else {
-                self.read_queue.push_back(e);
+                // Currently we drop unexpected responses here
+                // but it shouldn't happens since the sink interface
+                // is only SendRecv
+                #[cfg(feature = "tracing")]
+                {
+                    let body = e.get_body().map(|b| {
+                        String::from_utf8(b.to_vec()).unwrap_or("parsing error".to_string())
+                    });
+                    warn!(body, "recv'd unexpected response/reply")
+                }
             }
+        } else {
+            self.read_queue.push_back(Event::from(e));
         }
+        Poll::Ready(Ok(Some(())))
     }
 }
 
 impl<'a, S> Sink<Command<'a>> for ESLConnInner<S>
 where
-    S: AsyncWrite + AsyncRead + Unpin,
+    S: Unpin,
 {
     type Error = ESLError;
 
-    fn poll_close(
-        self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-    ) -> Poll<Result<(), Self::Error>> {
-        let me = self.project();
-        //ready!(me.poll_flush(cx))?;
-        me.stream.poll_close(cx)
-    }
-    fn poll_flush(
-        mut self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-    ) -> Poll<Result<(), Self::Error>> {
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let me = self.get_mut();
+        ready!(me.poll_write_buf(cx))?;
+        let res = ready!((me.driver.poll_close)(Pin::new(&mut me.stream), cx));
+        Poll::Ready(res.map_err(ESLError::from))
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let me = self.get_mut();
         loop {
-            match &mut self.active_write {
+            match &mut me.active_write {
                 None => return Poll::Ready(Ok(())),
-                Some(SendRecvState::Start) => {
-                    let me = self.as_mut().project();
-                    match ready!(me.stream.poll_flush(cx)) {
-                        Ok(_) => {
-                            *me.active_write = Some(SendRecvState::Pending(cx.waker().clone()));
-                            continue;
-                        }
-                        Err(e) => {
-                            // TODO:!!
-                            // should we reset the state on error ?
-                            return Poll::Ready(Err(e));
-                        }
+                Some(SendRecvState::Start) => match ready!(me.poll_write_buf(cx)) {
+                    Ok(_) => {
+                        me.active_write = Some(SendRecvState::Pending(cx.waker().clone()));
+                        continue;
                     }
-                }
+                    Err(e) => {
+                        // TODO:!!
+                        // should we reset the state on error ?
+                        return Poll::Ready(Err(e));
+                    }
+                },
                 Some(SendRecvState::Pending(w)) => {
                     // waker may be overwritten by other reads, so we record it here
                     *w = cx.waker().clone();
-                    match ready!(self.as_mut().poll_inner_stream(cx)) {
+                    let res = ready!(Pin::new(&mut *me).poll_inner_stream(cx));
+                    // reply may arrive just before the peer closes (ie. exit, auth denial)
+                    if matches!(me.active_write, Some(SendRecvState::Complete(_))) {
+                        continue;
+                    }
+                    match res {
                         Ok(None) => {
                             return Poll::Ready(Err(ESLError::new(
                                 crate::error::ErrorKind::ConnectionClosed,
                             )));
                         } // closed before reply
-                        Ok(Some(_)) => {
-                            if matches!(self.active_write, Some(SendRecvState::Complete(_))) {
-                                continue;
-                            } else {
-                                return Poll::Pending;
-                            };
-                        }
+                        Ok(Some(_)) => continue,
                         Err(e) => return Poll::Ready(Err(e)),
                     }
                 }
-                Some(SendRecvState::Complete(e)) => return Poll::Ready(Ok(())),
+                Some(SendRecvState::Complete(_)) => return Poll::Ready(Ok(())),
             }
         }
     }
-    fn start_send(self: std::pin::Pin<&mut Self>, item: Command<'a>) -> Result<(), Self::Error> {
-        let me = self.project();
-        *me.active_write = Some(SendRecvState::Start);
-        me.stream.start_send(item)
-    }
-    fn poll_ready(
-        self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-    ) -> Poll<Result<(), Self::Error>> {
-        // We must be able to 'flush' away any cancelled previous sends
-        if self.active_write.is_some() {
-            return self.poll_flush(cx);
+    fn start_send(self: Pin<&mut Self>, item: Command<'a>) -> Result<(), Self::Error> {
+        let me = self.get_mut();
+        me.active_write = Some(SendRecvState::Start);
+        me.codec.encode(item, &mut me.write_buf)
+    }
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let me = self.get_mut();
+        // A previous send_recv was dropped before taking its reply
+        loop {
+            match me.active_write {
+                None => break,
+                Some(SendRecvState::Start) => {
+                    // command may be partially written, so it must go out
+                    ready!(me.poll_write_buf(cx))?;
+                    me.active_write = None;
+                    me.discard += 1;
+                }
+                Some(SendRecvState::Pending(_)) => {
+                    me.active_write = None;
+                    me.discard += 1;
+                }
+                Some(SendRecvState::Complete(_)) => me.active_write = None,
+            }
         }
-        self.project().stream.poll_ready(cx)
+        Poll::Ready(Ok(()))
     }
 }
 
 impl<S> Stream for ESLConnInner<S>
 where
-    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    S: Unpin,
 {
-    type Item = RawEvent;
+    type Item = Result<Event, ESLError>;
 
-    fn poll_next(
-        mut self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Option<Self::Item>> {
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         loop {
             match self.as_mut().read_queue.pop_front() {
                 None => {
-                    // TODO: error
                     match ready!(self.as_mut().poll_inner_stream(cx)) {
                         Ok(r) => {
                             if !self.read_queue.is_empty() {
@@ -269,160 +761,27 @@ where
                             }
                             match r {
                                 None => return Poll::Ready(None),
-                                Some(()) => return Poll::Pending, // WHY IS THIS RETURNED?
+                                // frame was a reply, keep reading for events
+                                Some(()) => continue,
                             }
                         }
-                        Err(e) => continue, // TODO: how to handle rrors ..
+                        // the codec yields nothing further after an error
+                        Err(e) => return Poll::Ready(Some(Err(e))),
                     }
                 }
-                Some(e) => return Poll::Ready(Some(e)),
+                Some(e) => return Poll::Ready(Some(Ok(e))),
             }
         }
     }
 }
 
-// ==========================
-
-const MAX_HEADERS: usize = 32;
-const END: &[u8] = b"\r\n\r\n";
-
-enum ESLFrame {
-    Header(RawHeaders),
-    Body(Option<Bytes>),
-}
-
-pub(crate) struct RawHeaders(Vec<Bytes>);
-
-impl IntoIterator for RawHeaders {
-    type Item = Bytes;
-    type IntoIter = std::vec::IntoIter<Bytes>;
-    fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
-    }
-}
-
-impl RawHeaders {
-    fn new() -> Self {
-        Self(vec![])
-    }
-
-    fn append(&mut self, b: Bytes) {
-        if self.0.len() < MAX_HEADERS {
-            self.0.push(b);
-        }
-    }
-    pub(crate) fn get_header(&self, k: &str) -> Option<&Bytes> {
-        self.0.iter().find(|s| s.starts_with(k.as_bytes()))
-    }
-
-    pub(crate) fn get_content_len(&self) -> Option<usize> {
-        let header = self.get_header("Content-Length")?;
-        str::from_utf8(header)
-            .map(|s| s.split_once(":"))
-            .map(|s| s.and_then(|(_, b)| b.trim().parse::<usize>().ok()))
-            .unwrap_or_default()
-    }
-}
-
-struct ESLCodec {
-    decoder: ESLDecoder,
-}
-
-impl ESLCodec {
-    fn new() -> Self {
-        Self {
-            decoder: ESLDecoder::PendingHeader {
-                headers: RawHeaders::new(),
-                current: 0,
-            },
-        }
-    }
-}
-
-impl<'a> Encoder<Command<'a>> for ESLCodec {
-    type Error = ESLError;
-    fn encode(
-        &mut self,
-        item: Command<'a>,
-        dst: &mut tokio_util::bytes::BytesMut,
-    ) -> Result<(), Self::Error> {
-        let len = item.cmd.len() + item.args.len() + END.len();
-        dst.reserve(len);
-        dst.extend_from_slice(item.cmd.as_bytes());
-        dst.extend_from_slice(item.args.as_bytes());
-        dst.extend_from_slice(END);
-        Ok(())
-    }
-}
-
-enum ESLDecoder {
-    PendingHeader { headers: RawHeaders, current: usize },
-    PendingBody(usize),
-}
-
-impl Decoder for ESLCodec {
-    type Item = ESLFrame;
-    type Error = ESLError;
-
-    fn decode(
-        &mut self,
-        src: &mut tokio_util::bytes::BytesMut,
-    ) -> Result<Option<Self::Item>, Self::Error> {
-        match &mut self.decoder {
-            ESLDecoder::PendingHeader { current, headers } => loop {
-                let Some(n) = src[*current..].iter().position(|b| *b == b'\n') else {
-                    *current = src.len();
-                    return Ok(None);
-                };
-
-                // Position of newline in the full buffer
-                let newline_pos = *current + n;
-
-                // Extract line (everything up to the newline)
-                let line = src.split_to(newline_pos);
-
-                // Consume the newline character
-                src.split_to(1);
-
-                *current = 0;
-
-                if line.is_empty() {
-                    // Empty line signals end of headers
-                    let mut new_headers = RawHeaders::new();
-                    mem::swap(headers, &mut new_headers);
-                    if let Some(len) = new_headers.get_content_len() {
-                        self.decoder = ESLDecoder::PendingBody(len)
-                    }
-                    return Ok(Some(ESLFrame::Header(new_headers)));
-                } else {
-                    headers.append(line.freeze());
-                }
-            },
-            ESLDecoder::PendingBody(len) => {
-                if src.len() < *len {
-                    return Ok(None);
-                }
-                let buf = src.split_to(*len);
-                self.decoder = ESLDecoder::PendingHeader {
-                    headers: RawHeaders::new(),
-                    current: 0,
-                };
-                Ok(Some(ESLFrame::Body(Some(buf.freeze()))))
-            }
-        }
-    }
-}
-
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
+    use crate::event::PlainEvent;
     use indoc::indoc;
-    use tokio_stream::StreamExt;
     use tokio_test::io::Builder;
 
-    //const SESSION_DATA: &[u8] = include_bytes!("../tests/data/freeswitch_session.txt");
-    //const TEST_DATA_API_RES: &[u8] = include_bytes!("../tests/data/multi_command_and_api_response.txt");
-
     const RAW_EVENT: &[u8] = indoc! {b"
         Content-Length: 582
         Content-Type: text/event-plain
@@ -471,15 +830,301 @@ mod tests {
     #[tokio::test]
     async fn test_eslconn_basic_framing_multi_event() {
         let mock_stream = Builder::new().read(RAW_EVENT).read(RAW_EVENT).build();
-        let mut conn = ESLConnInner::new(mock_stream);
+        let mut conn = ESLConnInner::new(mock_stream, Driver::new(), CodecLimits::default());
 
         for _ in 0..2 {
             let event = conn.next().await;
             assert!(event.is_some(), "Expected to receive an event");
-            let event = event.unwrap();
+            let event = event.unwrap().unwrap();
             assert!(event.get_body().is_some(), "Expected body for this event");
             assert_eq!(event.get_header("Content-Type"), Some("text/event-plain"));
             assert!(event.get_body().unwrap().ends_with(b"db4edd065621"));
         }
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_eslconn_keepalive_probe() {
+        let mock_stream = Builder::new()
+            .write(b"api status\r\n\r\n")
+            .read(b"Content-Type: api/response\nContent-Length: 3\n\n+OK")
+            .read(RAW_EVENT)
+            .build();
+        let config = ESLConfig {
+            keepalive: Some(Keepalive {
+                interval: Duration::from_secs(10),
+                timeout: Duration::from_secs(5),
+            }),
+            ..Default::default()
+        };
+        let mut conn = ESLConnection::with_config(mock_stream, &config);
+        let event = conn.recv().await.unwrap();
+        assert_eq!(event.get_header("Content-Type"), Some("text/event-plain"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_eslconn_keepalive_timeout() {
+        let mock_stream = Builder::new()
+            .write(b"api status\r\n\r\n")
+            .wait(Duration::from_secs(60))
+            .build();
+        let config = ESLConfig {
+            keepalive: Some(Keepalive {
+                interval: Duration::from_secs(10),
+                timeout: Duration::from_secs(5),
+            }),
+            ..Default::default()
+        };
+        let mut conn = ESLConnection::with_config(mock_stream, &config);
+        let err = conn.recv().await.unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Timeout);
+        assert!(conn.is_disconnected());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_eslconn_timeout_discards_late_reply() {
+        let mock_stream = Builder::new()
+            .write(b"api status\r\n\r\n")
+            .write(b"api version\r\n\r\n")
+            .wait(Duration::from_secs(10))
+            .read(b"Content-Type: api/response\nContent-Length: 5\n\nfirst")
+            .read(b"Content-Type: api/response\nContent-Length: 6\n\nsecond")
+            .build();
+        let mut conn = ESLConnection::new(mock_stream);
+
+        let err = conn
+            .send_recv_timeout(Command::api("status"), Duration::from_secs(1))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Timeout);
+
+        let reply = conn.send_recv(Command::api("version")).await.unwrap();
+        assert_eq!(reply.get_body().unwrap().as_ref(), b"second");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_eslconn_dropped_mid_flush() {
+        // Writes are blocked, so the first command is dropped whilst still flushing
+        let mock_stream = Builder::new()
+            .wait(Duration::from_secs(5))
+            .write(b"api status\r\n\r\n")
+            .write(b"api version\r\n\r\n")
+            .read(b"Content-Type: api/response\nContent-Length: 5\n\nfirst")
+            .read(b"Content-Type: api/response\nContent-Length: 6\n\nsecond")
+            .build();
+        let mut conn = ESLConnection::new(mock_stream);
+
+        let res = tokio::time::timeout(
+            Duration::from_secs(1),
+            conn.send_recv(Command::api("status")),
+        )
+        .await;
+        assert!(res.is_err());
+
+        let reply = conn.send_recv(Command::api("version")).await.unwrap();
+        assert_eq!(reply.get_body().unwrap().as_ref(), b"second");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_eslconn_default_command_timeout() {
+        let mock_stream = Builder::new()
+            .write(b"api status\r\n\r\n")
+            .wait(Duration::from_secs(10))
+            .read(b"Content-Type: api/response\nContent-Length: 5\n\nfirst")
+            .read(RAW_EVENT)
+            .build();
+        let config = ESLConfig {
+            command_timeout: Some(Duration::from_secs(2)),
+            ..Default::default()
+        };
+        let mut conn = ESLConnection::with_config(mock_stream, &config);
+
+        let err = conn.send_recv(Command::api("status")).await.unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Timeout);
+        assert!(!conn.is_disconnected());
+
+        // late reply must not surface as an event either
+        let event = conn.recv().await.unwrap();
+        assert_eq!(event.get_header("Content-Type"), Some("text/event-plain"));
+    }
+
+    const EXIT_REPLY: &[u8] = b"Content-Type: command/reply\nReply-Text: +OK bye\n\n";
+
+    #[tokio::test]
+    async fn test_eslconn_shutdown_collects_events() {
+        let mock_stream = Builder::new()
+            .write(b"exit \r\n\r\n")
+            .read(RAW_EVENT)
+            .read(EXIT_REPLY)
+            .read(RAW_EVENT)
+            .build();
+        let mut conn = ESLConnection::new(mock_stream);
+
+        let report = conn.shutdown(ShutdownOptions::default()).await;
+        assert!(report.result.is_ok(), "{:?}", report.result);
+        assert!(report.exit_reply.is_some_and(|r| r.is_ok()));
+        assert_eq!(report.events.len(), 2);
+        assert!(conn.is_disconnected());
+
+        let err = conn.send_recv(Command::api("status")).await.unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ConnectionClosed);
+    }
+
+    #[tokio::test]
+    async fn test_eslconn_shutdown_keeps_events() {
+        let mock_stream = Builder::new()
+            .write(b"exit \r\n\r\n")
+            .read(EXIT_REPLY)
+            .read(RAW_EVENT)
+            .build();
+        let mut conn = ESLConnection::new(mock_stream);
+
+        let options = ShutdownOptions {
+            drain: Drain::Keep,
+            ..Default::default()
+        };
+        let report = conn.shutdown(options).await;
+        assert!(report.result.is_ok());
+        assert!(report.events.is_empty());
+
+        assert!(conn.recv().await.is_ok());
+        let err = conn.recv().await.unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ConnectionClosed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_eslconn_shutdown_timeout() {
+        let mock_stream = Builder::new()
+            .write(b"exit \r\n\r\n")
+            .read(EXIT_REPLY)
+            .wait(Duration::from_secs(60))
+            .build();
+        let config = ESLConfig {
+            disconnect_timeout: Duration::from_secs(2),
+            ..Default::default()
+        };
+        let mut conn = ESLConnection::with_config(mock_stream, &config);
+
+        let report = conn.shutdown(ShutdownOptions::default()).await;
+        assert!(report.exit_reply.is_some());
+        assert_eq!(report.result.unwrap_err().kind(), &ErrorKind::Timeout);
+    }
+
+    #[tokio::test]
+    async fn test_eslconn_recv_protocol_violation() {
+        let mock_stream = Builder::new()
+            .read(b"Content-Type: text/event-plain\nContent-Length: 4096\n\n")
+            .build();
+        let config = ESLConfig {
+            limits: CodecLimits {
+                max_body_size: 1024,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut conn = ESLConnection::with_config(mock_stream, &config);
+
+        let err = conn.recv().await.unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ProtocolViolation("body too large"));
+        assert_eq!(
+            conn.recv().await.unwrap_err().kind(),
+            &ErrorKind::ConnectionClosed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_eslconn_tracks_subscription() {
+        use crate::EventName::*;
+        let mock_stream = Builder::new()
+            .write(b"event plain CHANNEL_CREATE HEARTBEAT\r\n\r\n")
+            .read(b"Content-Type: command/reply\nReply-Text: +OK event listener enabled plain\n\n")
+            .write(b"event plain DTMF\r\n\r\n")
+            .read(b"Content-Type: command/reply\nReply-Text: -ERR no permission\n\n")
+            .write(b"nixevent HEARTBEAT\r\n\r\n")
+            .read(b"Content-Type: command/reply\nReply-Text: +OK events nixed\n\n")
+            .build();
+        let mut conn = ESLConnection::new(mock_stream);
+
+        let sub = EventSubscription::new().events([ChannelCreate, Heartbeat]);
+        assert!(conn.subscribe(&sub).await.unwrap().is_ok());
+        let denied = EventSubscription::new().events([Dtmf]);
+        assert!(!conn.subscribe(&denied).await.unwrap().is_ok());
+        let nix = EventSubscription::new().events([Heartbeat]);
+        assert!(conn.unsubscribe(&nix).await.unwrap().is_ok());
+
+        assert_eq!(conn.subscription().get_events(), &[ChannelCreate]);
+    }
+
+    #[tokio::test]
+    async fn test_eslconn_tracks_filters() {
+        const OK: &[u8] = b"Content-Type: command/reply\nReply-Text: +OK filter added\n\n";
+        let mock_stream = Builder::new()
+            .write(b"filter Unique-ID abc123\r\n\r\n")
+            .read(OK)
+            .write(b"filter Event-Name /^CHANNEL_/\r\n\r\n")
+            .read(OK)
+            .write(b"filter delete Unique-ID abc123\r\n\r\n")
+            .read(OK)
+            .write(b"filter delete all \r\n\r\n")
+            .read(OK)
+            .build();
+        let mut conn = ESLConnection::new(mock_stream);
+
+        conn.filter_unique_id("abc123").await.unwrap();
+        let channels = Filter::regex("Event-Name", "^CHANNEL_");
+        conn.add_filter(&channels).await.unwrap();
+        conn.delete_filter(&Filter::unique_id("abc123"))
+            .await
+            .unwrap();
+        assert_eq!(conn.filters(), &[channels]);
+
+        conn.delete_all_filters().await.unwrap();
+        assert!(conn.filters().is_empty());
+    }
+
+    fn plain_frame(body: &str) -> Vec<u8> {
+        format!(
+            "Content-Length: {}\nContent-Type: text/event-plain\n\n{body}",
+            body.len()
+        )
+        .into_bytes()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_eslconn_wait_for_keeps_other_events() {
+        use crate::EventName::*;
+        let mock_stream = Builder::new()
+            .read(&plain_frame("Event-Name: HEARTBEAT\n\n"))
+            .read(&plain_frame(
+                "Event-Name: CHANNEL_ANSWER\nUnique-ID: abc\n\n",
+            ))
+            .read(&plain_frame("Event-Name: DTMF\n\n"))
+            .wait(Duration::from_secs(60))
+            .build();
+        let mut conn = ESLConnection::new(mock_stream);
+        let name = |e: Event| {
+            let plain = PlainEvent::try_from(e).unwrap();
+            plain.get_header("Event-Name").unwrap().to_string()
+        };
+
+        let answered = EventMatcher::new()
+            .event_name(ChannelAnswer)
+            .unique_id("abc");
+        let event = conn
+            .wait_for(&answered, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(name(event), "CHANNEL_ANSWER");
+
+        let err = conn
+            .wait_for(
+                &EventMatcher::new().event_name(ChannelHangup),
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Timeout);
+
+        assert_eq!(name(conn.recv().await.unwrap()), "HEARTBEAT");
+        assert_eq!(name(conn.recv().await.unwrap()), "DTMF");
+    }
 }
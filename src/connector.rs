@@ -0,0 +1,227 @@
+#[cfg(any(feature = "tokio", feature = "smol"))]
+use socket2::{SockRef, TcpKeepalive};
+#[cfg(any(feature = "tokio", feature = "smol"))]
+use std::time::Duration;
+use std::{future::Future, io};
+#[cfg(feature = "tokio")]
+use tokio::net::{TcpStream, ToSocketAddrs};
+
+/// Establishes the transport used by [`Inbound::connect_with`](crate::Inbound::connect_with).
+///
+/// Implement this to run the ESL handshake over any stream, ie. an SSH tunnel
+/// or SOCKS proxy. Closures returning a stream future implement it as well.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "tokio")]
+/// use eslrs::Inbound;
+/// # #[cfg(feature = "tokio")]
+/// use tokio::net::TcpStream;
+///
+/// # #[cfg(feature = "tokio")]
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let connector = || TcpStream::connect("127.0.0.1:8021");
+/// let mut conn = Inbound::connect_with(connector, "ClueCon").await.unwrap();
+/// # }
+/// # #[cfg(not(feature = "tokio"))]
+/// # fn main() {}
+/// ```
+pub trait Connector {
+    type Stream: Unpin;
+
+    fn connect(self) -> impl Future<Output = io::Result<Self::Stream>>;
+}
+
+impl<F, Fut, S> Connector for F
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = io::Result<S>>,
+    S: Unpin,
+{
+    type Stream = S;
+
+    fn connect(self) -> impl Future<Output = io::Result<Self::Stream>> {
+        self()
+    }
+}
+
+/// Connects over TCP, used by [`Inbound::connect`](crate::Inbound::connect).
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone)]
+pub struct TcpConnector<A> {
+    addr: A,
+    keepalive: Option<Duration>,
+}
+
+#[cfg(feature = "tokio")]
+impl<A> TcpConnector<A> {
+    pub fn new(addr: A) -> Self {
+        Self {
+            addr,
+            keepalive: None,
+        }
+    }
+
+    /// Enables TCP keepalive on the socket with the given idle time.
+    pub fn keepalive(mut self, keepalive: Option<Duration>) -> Self {
+        self.keepalive = keepalive;
+        self
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<A: ToSocketAddrs> Connector for TcpConnector<A> {
+    type Stream = TcpStream;
+
+    async fn connect(self) -> io::Result<Self::Stream> {
+        let stream = TcpStream::connect(self.addr).await?;
+        if let Some(time) = self.keepalive {
+            SockRef::from(&stream).set_tcp_keepalive(&TcpKeepalive::new().with_time(time))?;
+        }
+        Ok(stream)
+    }
+}
+
+/// Connects over a Unix domain socket, ie. when FreeSWITCH is reached via a local socket proxy.
+///
+/// # Examples
+///
+/// ```no_run
+/// use eslrs::{Inbound, UnixConnector};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let connector = UnixConnector("/run/freeswitch/esl.sock".into());
+/// let mut conn = Inbound::connect_with(connector, "ClueCon").await.unwrap();
+/// # }
+/// ```
+#[cfg(all(unix, feature = "tokio"))]
+#[derive(Debug, Clone)]
+pub struct UnixConnector(pub std::path::PathBuf);
+
+#[cfg(all(unix, feature = "tokio"))]
+impl Connector for UnixConnector {
+    type Stream = tokio::net::UnixStream;
+
+    fn connect(self) -> impl Future<Output = io::Result<Self::Stream>> {
+        tokio::net::UnixStream::connect(self.0)
+    }
+}
+
+/// Connects over TCP using [`async-net`](async_net), for the [`Smol`](crate::rt::Smol) runtime.
+///
+/// # Examples
+///
+/// ```no_run
+/// use eslrs::{Inbound, SmolConnector};
+///
+/// # async fn example() {
+/// let connector = SmolConnector::new("127.0.0.1:8021");
+/// let mut conn = Inbound::connect_with(connector, "ClueCon").await.unwrap();
+/// # }
+/// ```
+#[cfg(feature = "smol")]
+#[derive(Debug, Clone)]
+pub struct SmolConnector<A> {
+    addr: A,
+    keepalive: Option<Duration>,
+}
+
+#[cfg(feature = "smol")]
+impl<A> SmolConnector<A> {
+    pub fn new(addr: A) -> Self {
+        Self {
+            addr,
+            keepalive: None,
+        }
+    }
+
+    /// Enables TCP keepalive on the socket with the given idle time.
+    pub fn keepalive(mut self, keepalive: Option<Duration>) -> Self {
+        self.keepalive = keepalive;
+        self
+    }
+}
+
+#[cfg(feature = "smol")]
+impl<A: async_net::AsyncToSocketAddrs> Connector for SmolConnector<A> {
+    type Stream = async_net::TcpStream;
+
+    async fn connect(self) -> io::Result<Self::Stream> {
+        let stream = async_net::TcpStream::connect(self.addr).await?;
+        if let Some(time) = self.keepalive {
+            SockRef::from(&stream).set_tcp_keepalive(&TcpKeepalive::new().with_time(time))?;
+        }
+        Ok(stream)
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::Inbound;
+    use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+    async fn serve_auth<S: AsyncRead + AsyncWrite + Unpin>(socket: S) -> String {
+        let mut socket = BufReader::new(socket);
+        socket
+            .write_all(b"Content-Type: auth/request\n\n")
+            .await
+            .unwrap();
+        let mut line = String::new();
+        socket.read_line(&mut line).await.unwrap();
+        socket
+            .write_all(b"Content-Type: command/reply\nReply-Text: +OK accepted\n\n")
+            .await
+            .unwrap();
+        line.trim().to_string()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_connect_with_unix_socket() {
+        let path = std::env::temp_dir().join(format!("eslrs-test-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            serve_auth(socket).await
+        });
+
+        let conn = Inbound::connect_with(UnixConnector(path.clone()), "ClueCon").await;
+        assert!(conn.is_ok(), "{:?}", conn.err());
+        assert_eq!(server.await.unwrap(), "auth ClueCon");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_connect_with_closure() {
+        let (client, server) = tokio::io::duplex(1024);
+        let server = tokio::spawn(serve_auth(server));
+
+        let conn = Inbound::connect_with(|| async { Ok(client) }, "ClueCon").await;
+        assert!(conn.is_ok(), "{:?}", conn.err());
+        assert_eq!(server.await.unwrap(), "auth ClueCon");
+    }
+
+    #[tokio::test]
+    async fn test_tcp_connector_keepalive() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let stream = TcpConnector::new(addr)
+            .keepalive(Some(std::time::Duration::from_secs(30)))
+            .connect()
+            .await
+            .unwrap();
+        assert!(SockRef::from(&stream).keepalive().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_connect_future_is_send() {
+        // Connection futures must remain spawnable
+        let handle = tokio::spawn(Inbound::connect("127.0.0.1:1", "ClueCon"));
+        assert!(handle.await.unwrap().is_err());
+    }
+}
@@ -12,6 +12,15 @@ pub enum ErrorKind {
     /// for more debug info
     IO,
 
+    /// Peer did not respond within the configured time.
+    /// Failed keepalive probes also close the connection
+    Timeout,
+
+    /// Peer sent a frame exceeding the configured
+    /// [`CodecLimits`](crate::codec::CodecLimits) or otherwise malformed.
+    /// The connection is no longer usable
+    ProtocolViolation(&'static str),
+
     /// Should never happen, please report via github issue
     InternalError(&'static str),
 }
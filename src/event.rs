@@ -4,36 +4,216 @@ mod reply;
 
 pub use builder::*;
 pub use format::*;
-use multimap::MultiMap;
 pub use reply::Reply;
 
-use crate::connection::RawHeaders;
+use std::{borrow::Cow, fmt::Write, ops::Range, sync::OnceLock};
+
+use crate::Command;
+
+/// Headers in arrival order, looked up by exact name ignoring ASCII case.
+///
+/// Holds the raw `Name: value` block as received and only indexes line
+/// offsets into it on first access, so events nobody inspects cost a
+/// single UTF-8 check. Frames carry tens of headers, so a linear scan
+/// over the index beats hashing and never allocates on lookup.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct HeaderMap {
+    block: Bytes,
+    utf8: bool,
+    index: OnceLock<Vec<Span>>,
+}
 
 #[derive(Clone, Debug)]
-pub(crate) struct HeaderMap(MultiMap<Bytes, Bytes>);
+struct Span {
+    name: Range<usize>,
+    value: Range<usize>,
+}
+
 impl HeaderMap {
-    pub(crate) fn new(headers: RawHeaders) -> Self {
-        let mut map = MultiMap::new();
-        for h in headers {
-            let Some(n) = h.iter().position(|c| *c == b':') else {
-                continue;
-            };
-            let k = h.slice_ref(&h[..n]);
-            let v = if h.len() > n {
-                h.slice_ref(h[n + 1..].trim_ascii())
-            } else {
-                Bytes::new()
-            };
-            map.insert(k, v);
+    pub(crate) fn parse(block: Bytes) -> Self {
+        Self {
+            utf8: str::from_utf8(&block).is_ok(),
+            block,
+            index: OnceLock::new(),
+        }
+    }
+
+    fn index(&self) -> &[Span] {
+        self.index.get_or_init(|| {
+            let mut spans = vec![];
+            let mut start = 0;
+            for line in self.block.split(|c| *c == b'\n') {
+                let offset = start;
+                start += line.len() + 1;
+                let Some(n) = line.iter().position(|c| *c == b':') else {
+                    continue;
+                };
+                let name = line[..n].trim_ascii();
+                let value = line[n + 1..].trim_ascii();
+                spans.push(Span {
+                    name: self.range_of(offset, line, name),
+                    value: self.range_of(offset, line, value),
+                });
+            }
+            spans
+        })
+    }
+
+    fn range_of(&self, offset: usize, line: &[u8], part: &[u8]) -> Range<usize> {
+        let start = offset + (part.as_ptr() as usize - line.as_ptr() as usize);
+        start..start + part.len()
+    }
+
+    fn str_at(&self, range: &Range<usize>) -> &str {
+        let b = &self.block[range.clone()];
+        if self.utf8 {
+            // SAFETY: `utf8` is only set when `HeaderMap::parse` validated
+            // the whole block with `str::from_utf8`. Spans start and end at
+            // the block's ends or next to the ASCII bytes `\n`, `:` and
+            // whitespace, which are char boundaries, so each is valid UTF-8.
+            unsafe { str::from_utf8_unchecked(b) }
+        } else {
+            str::from_utf8(b).unwrap_or("INVALID UTF8")
         }
-        HeaderMap(map)
+    }
+
+    fn matching<'a>(&'a self, k: &str) -> impl Iterator<Item = &'a str> {
+        self.index()
+            .iter()
+            .filter(move |s| self.block[s.name.clone()].eq_ignore_ascii_case(k.as_bytes()))
+            .map(|s| self.str_at(&s.value))
     }
 
     pub fn get_header(&self, k: &str) -> Option<&str> {
-        let b = Bytes::copy_from_slice(k.as_ref());
-        self.0
-            .get(&b)
-            .map(|b| str::from_utf8(b).unwrap_or("INVALID UTF8"))
+        self.matching(k).next()
+    }
+
+    pub fn get_all(&self, k: &str) -> Vec<&str> {
+        self.matching(k).collect()
+    }
+
+    pub fn headers(&self) -> Headers<'_> {
+        Headers {
+            map: self,
+            spans: self.index().iter(),
+        }
+    }
+}
+
+/// Iterator over `(name, value)` header pairs in the order they were received.
+///
+/// Created by `headers()` on [`Event`], [`PlainEvent`] and [`Reply`].
+#[derive(Clone, Debug)]
+pub struct Headers<'a> {
+    map: &'a HeaderMap,
+    spans: std::slice::Iter<'a, Span>,
+}
+
+impl<'a> Iterator for Headers<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.spans
+            .next()
+            .map(|s| (self.map.str_at(&s.name), self.map.str_at(&s.value)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.spans.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Headers<'_> {}
+
+/// Splits a FreeSWITCH array value, ie. `ARRAY::a|:b|:c`, into its elements.
+///
+/// Values without the `ARRAY::` prefix are returned as a single element.
+/// Plain events percent-encode their values, decode them first.
+///
+/// # Examples
+///
+/// ```
+/// use eslrs::event::parse_array;
+///
+/// assert_eq!(parse_array("ARRAY::a|:b|:c"), vec!["a", "b", "c"]);
+/// assert_eq!(parse_array("single"), vec!["single"]);
+/// ```
+pub fn parse_array(value: &str) -> Vec<&str> {
+    match value.strip_prefix("ARRAY::") {
+        Some(items) => items.split("|:").collect(),
+        None => vec![value],
+    }
+}
+
+/// Decodes a percent-encoded plain event value, ie. `Hello%20World`.
+///
+/// Invalid escapes are kept as is, values without any are borrowed.
+///
+/// # Examples
+///
+/// ```
+/// use eslrs::event::percent_decode;
+///
+/// assert_eq!(percent_decode("Hello%20World"), "Hello World");
+/// assert_eq!(percent_decode("100%"), "100%");
+/// ```
+pub fn percent_decode(s: &str) -> Cow<'_, str> {
+    if !s.contains('%') {
+        return Cow::Borrowed(s);
+    }
+    let b = s.as_bytes();
+    let mut out = Vec::with_capacity(b.len());
+    let mut i = 0;
+    while i < b.len() {
+        let hex = b
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok());
+        match (b[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(c)) => {
+                out.push(c);
+                i += 3;
+            }
+            (c, _) => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    Cow::Owned(String::from_utf8_lossy(&out).into_owned())
+}
+
+/// Percent-encodes a value the way FreeSWITCH does for plain events.
+///
+/// Everything but ASCII letters, digits and `-_.~` is encoded.
+///
+/// # Examples
+///
+/// ```
+/// use eslrs::event::percent_encode;
+///
+/// assert_eq!(percent_encode("Hello World"), "Hello%20World");
+/// ```
+pub fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char)
+            }
+            b => {
+                let _ = write!(out, "%{b:02X}");
+            }
+        }
+    }
+    out
+}
+
+// Values are sent unencoded with `sendevent`, as libesl does,
+// only line breaks are encoded to keep the framing intact.
+pub(crate) fn escape_line_breaks(s: &str) -> Cow<'_, str> {
+    match s.contains(['\r', '\n']) {
+        true => Cow::Owned(s.replace('\r', "%0D").replace('\n', "%0A")),
+        false => Cow::Borrowed(s),
     }
 }
 
@@ -41,12 +221,18 @@ impl HeaderMap {
 pub(crate) struct RawEvent(pub(crate) HeaderMap, pub(crate) Option<Bytes>);
 
 impl RawEvent {
-    pub(crate) fn new(headers: RawHeaders, b: Option<Bytes>) -> Self {
-        Self(HeaderMap::new(headers), b)
+    pub(crate) fn new(headers: HeaderMap, b: Option<Bytes>) -> Self {
+        Self(headers, b)
     }
     pub(crate) fn get_header(&self, header: &str) -> Option<&str> {
         self.0.get_header(header)
     }
+    pub(crate) fn get_all(&self, header: &str) -> Vec<&str> {
+        self.0.get_all(header)
+    }
+    pub(crate) fn headers(&self) -> Headers<'_> {
+        self.0.headers()
+    }
     pub(crate) fn get_body(&self) -> Option<&Bytes> {
         self.1.as_ref()
     }
@@ -62,7 +248,9 @@ impl RawEvent {
 }
 
 #[derive(Clone, Debug)]
-pub struct Event(RawEvent);
+pub struct Event(pub(crate) RawEvent);
+
+type Fields = (Vec<(String, String)>, Option<Bytes>);
 
 impl From<RawEvent> for Event {
     fn from(value: RawEvent) -> Self {
@@ -72,6 +260,7 @@ impl From<RawEvent> for Event {
 
 macro_rules! impl_tryfrom {
     ($i:ident) => {
+        #[allow(clippy::infallible_try_from)]
         impl TryFrom<Event> for $i {
             type Error = <$i as EventFormat>::Error;
             fn try_from(value: Event) -> Result<Self, Self::Error> {
@@ -100,6 +289,8 @@ pub(crate) use delegate;
 
 impl Event {
     delegate!(get_header (header: str) -> Option<&str> );
+    delegate!(get_all (header: str) -> Vec<&str> );
+    delegate!(headers () -> Headers<'_> );
     delegate!(get_body () -> Option<&Bytes> );
     delegate!(get_content_type() -> Option<&str> );
 
@@ -128,4 +319,228 @@ impl Event {
             .map(|s| s.starts_with(JsonEvent::CONTENT_TYPE))
             .unwrap_or_default()
     }
+
+    /// The event in `text/event-plain` format, ie. to store it or forward it
+    /// to another ESL peer.
+    ///
+    /// Plain events are returned as received. JSON events are re-encoded with
+    /// percent-encoded values, arrays as `ARRAY::` values and a
+    /// `Content-Length` counting the body's bytes. `None` if this frame isn't
+    /// a plain or JSON event, ie. a `log/data` line. XML events aren't
+    /// supported.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use eslrs::event::{Event, PlainEvent};
+    /// # fn example(event: Event) {
+    /// if let Some(plain) = event.to_plain_bytes() {
+    ///     std::fs::write("event.txt", &plain).unwrap();
+    /// }
+    /// # }
+    /// ```
+    pub fn to_plain_bytes(&self) -> Option<Bytes> {
+        if self.is_plain_event() {
+            return Some(self.get_body().cloned().unwrap_or_default());
+        }
+        let (headers, body) = self.fields()?;
+        let mut out = String::new();
+        for (name, value) in &headers {
+            let _ = writeln!(out, "{name}: {}", percent_encode(value));
+        }
+        let mut out = out.into_bytes();
+        match body {
+            Some(body) => {
+                out.extend_from_slice(format!("Content-Length: {}\n\n", body.len()).as_bytes());
+                out.extend_from_slice(&body);
+            }
+            None => out.push(b'\n'),
+        }
+        Some(out.into())
+    }
+
+    /// The event in the shape of a JSON event, see [`PlainEvent::to_json`].
+    ///
+    /// `None` if this frame isn't a plain or JSON event.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Option<JsonEvent> {
+        if self.is_json() {
+            return JsonEvent::try_from(self.clone()).ok();
+        }
+        if self.is_plain_event() {
+            return PlainEvent::try_from(self.clone()).ok().map(|e| e.to_json());
+        }
+        None
+    }
+
+    /// A `sendevent` command firing this event again.
+    ///
+    /// The name, headers and body are kept, with `Content-Length` recomputed
+    /// over the body's bytes. Values are sent decoded, as FreeSWITCH expects,
+    /// with line breaks percent-encoded. `None` if this frame isn't a plain
+    /// or JSON event.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "tokio")]
+    /// use eslrs::{EventName, EventSubscription, Inbound};
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let mut source = Inbound::connect("10.0.0.1:8021", "ClueCon").await.unwrap();
+    /// let mut target = Inbound::connect("10.0.0.2:8021", "ClueCon").await.unwrap();
+    /// source.subscribe(&EventSubscription::new().custom(["sms::recv"])).await.unwrap();
+    /// loop {
+    ///     let event = source.recv().await.unwrap();
+    ///     if let Some(cmd) = event.sendevent_command() {
+    ///         target.send_recv(cmd).await.unwrap();
+    ///     }
+    /// }
+    /// # }
+    /// # #[cfg(not(feature = "tokio"))]
+    /// # fn main() {}
+    /// ```
+    pub fn sendevent_command(&self) -> Option<Command<'static>> {
+        let (headers, body) = self.fields()?;
+        let name = headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("Event-Name"))
+            .map(|(_, v)| v.as_str())?;
+        let mut out = format!("{}\n", escape_line_breaks(name));
+        for (k, v) in &headers {
+            if !k.eq_ignore_ascii_case("Event-Name") {
+                let _ = writeln!(out, "{k}: {}", escape_line_breaks(v));
+            }
+        }
+        let cmd = Command::sendevent(out);
+        Some(match body {
+            Some(body) => cmd.body(body),
+            None => cmd,
+        })
+    }
+
+    // Decoded headers, without Content-Length, and body of a plain or JSON event
+    fn fields(&self) -> Option<Fields> {
+        if self.is_plain_event() {
+            let plain = PlainEvent::try_from(self.clone()).ok()?;
+            let headers = plain
+                .headers()
+                .filter(|(k, _)| !k.eq_ignore_ascii_case("Content-Length"))
+                .map(|(k, v)| (k.to_string(), percent_decode(v).into_owned()))
+                .collect();
+            return Some((headers, plain.get_body().cloned()));
+        }
+        #[cfg(feature = "json")]
+        if self.is_json() {
+            let JsonEvent::Object(json) = JsonEvent::try_from(self.clone()).ok()? else {
+                return None;
+            };
+            let mut headers = vec![];
+            let mut body = None;
+            for (k, v) in json {
+                let value = match v {
+                    serde_json::Value::String(s) => s,
+                    serde_json::Value::Array(items) => {
+                        let items: Vec<String> = items
+                            .into_iter()
+                            .map(|i| match i {
+                                serde_json::Value::String(s) => s,
+                                i => i.to_string(),
+                            })
+                            .collect();
+                        format!("ARRAY::{}", items.join("|:"))
+                    }
+                    v => v.to_string(),
+                };
+                if k == "_body" {
+                    body = Some(Bytes::from(value));
+                } else if !k.eq_ignore_ascii_case("Content-Length") {
+                    headers.push((k, value));
+                }
+            }
+            return Some((headers, body));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::ESLCodec;
+    use bytes::BytesMut;
+
+    fn frame(content_type: &str, body: &str) -> Event {
+        let frame = format!(
+            "Content-Length: {}\nContent-Type: {content_type}\n\n{body}",
+            body.len()
+        );
+        ESLCodec::new()
+            .decode(&mut BytesMut::from(frame.as_str()))
+            .unwrap()
+            .unwrap()
+    }
+
+    fn encoded(cmd: Command<'_>) -> BytesMut {
+        let mut dst = BytesMut::new();
+        ESLCodec::new().encode(cmd, &mut dst).unwrap();
+        dst
+    }
+
+    const PLAIN: &str = "Event-Name: CUSTOM\nEvent-Subclass: sms%3A%3Arecv\n\
+        Codecs: ARRAY%3A%3APCMU%7C%3APCMA\nContent-Length: 6\n\nh\u{e9}llo";
+
+    #[test]
+    fn test_plain_round_trip() {
+        let event = frame("text/event-plain", PLAIN);
+        let plain = event.to_plain_bytes().unwrap();
+        assert_eq!(plain.as_ref(), PLAIN.as_bytes());
+
+        let again = frame("text/event-plain", str::from_utf8(&plain).unwrap());
+        assert_eq!(again.to_plain_bytes(), Some(plain));
+    }
+
+    #[test]
+    fn test_sendevent_command() {
+        let event = frame("text/event-plain", PLAIN);
+        assert_eq!(
+            encoded(event.sendevent_command().unwrap()).as_ref(),
+            "sendevent CUSTOM\nEvent-Subclass: sms::recv\nCodecs: ARRAY::PCMU|:PCMA\n\
+            Content-Length: 6\n\nh\u{e9}llo\r\n\r\n"
+                .as_bytes()
+        );
+
+        let event = frame(
+            "text/event-plain",
+            "Event-Name: HEARTBEAT\nInfo: two%0Alines\n\n",
+        );
+        assert_eq!(
+            encoded(event.sendevent_command().unwrap()).as_ref(),
+            b"sendevent HEARTBEAT\nInfo: two%0Alines\n\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_not_an_event() {
+        let log = frame("log/data", "a log line\n");
+        assert!(log.to_plain_bytes().is_none());
+        assert!(log.sendevent_command().is_none());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_round_trip() {
+        let json = r#"{"Event-Name":"CUSTOM","Event-Subclass":"sms::recv","Codecs":["PCMU","PCMA"],"_body":"héllo"}"#;
+        let event = frame("text/event-json", json);
+
+        let plain = event.to_plain_bytes().unwrap();
+        let plain = frame("text/event-plain", str::from_utf8(&plain).unwrap());
+        assert_eq!(plain.to_json(), event.to_json());
+        assert_eq!(
+            encoded(plain.sendevent_command().unwrap()),
+            encoded(event.sendevent_command().unwrap())
+        );
+    }
 }
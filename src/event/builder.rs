@@ -1,10 +1,207 @@
-/// Creates an event string to send over esl connection.
+use std::fmt::{self, Display, Write};
+
+use bytes::Bytes;
+
+use crate::{Command, event::escape_line_breaks};
+
+/// An event to fire with `sendevent`.
+///
+/// Header names are checked and `Content-Length` is computed over the body's
+/// bytes when converting into a [`Command`]. Values are sent unencoded, as
+/// FreeSWITCH expects, with line breaks percent-encoded to keep the framing intact.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "tokio")]
+/// use eslrs::{EventName, Inbound, event::{OutgoingEvent, Priority}};
+///
+/// # #[cfg(feature = "tokio")]
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let mut conn = Inbound::connect("0.0.0.0:8021", "ClueCon").await.unwrap();
+///
+/// let notify = OutgoingEvent::new(EventName::Notify)
+///     .header("profile", "internal")
+///     .header("user", 1000)
+///     .priority(Priority::High)
+///     .body("Messages-Waiting: yes\r\n", "application/simple-message-summary");
+/// conn.send_recv(notify.command().unwrap()).await.unwrap();
+///
+/// let custom = OutgoingEvent::custom("myapp::ready");
+/// conn.send_recv(custom.command().unwrap()).await.unwrap();
+/// # }
+/// # #[cfg(not(feature = "tokio"))]
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutgoingEvent {
+    name: String,
+    headers: Vec<(String, String)>,
+    body: Option<(Bytes, String)>,
+}
+
+/// Priority FreeSWITCH delivers an event with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Priority {
+    #[default]
+    Normal,
+    Low,
+    High,
+}
+
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::Normal => "NORMAL",
+            Priority::Low => "LOW",
+            Priority::High => "HIGH",
+        }
+    }
+}
+
+/// Returned when an [`OutgoingEvent`] can't be converted into a command.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum OutgoingEventError {
+    /// The event name is empty or contains whitespace
+    InvalidEventName(String),
+    /// The header name is empty, contains `:`, whitespace or control
+    /// characters, or is `Content-Length`, which is computed from the body
+    InvalidHeaderName(String),
+}
+
+impl Display for OutgoingEventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutgoingEventError::InvalidEventName(name) => write!(f, "invalid event name: {name:?}"),
+            OutgoingEventError::InvalidHeaderName(name) => {
+                write!(f, "invalid header name: {name:?}")
+            }
+        }
+    }
+}
+impl std::error::Error for OutgoingEventError {}
+
+impl OutgoingEvent {
+    /// An event named `name`, ie. an [`EventName`](crate::EventName).
+    pub fn new(name: impl Display) -> Self {
+        Self {
+            name: name.to_string(),
+            headers: vec![],
+            body: None,
+        }
+    }
+
+    /// A `CUSTOM` event of the given subclass.
+    pub fn custom(subclass: impl Into<String>) -> Self {
+        Self::new("CUSTOM").custom_subclass(subclass)
+    }
+
+    /// Adds a header, repeated names are sent as given.
+    pub fn header(mut self, name: impl Into<String>, value: impl Display) -> Self {
+        self.headers.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Makes this a `CUSTOM` event of the given subclass, ie. `myapp::ready`.
+    pub fn custom_subclass(mut self, subclass: impl Into<String>) -> Self {
+        self.name = "CUSTOM".to_string();
+        self.headers
+            .retain(|(k, _)| !k.eq_ignore_ascii_case("Event-Subclass"));
+        self.header("Event-Subclass", subclass.into())
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.headers
+            .retain(|(k, _)| !k.eq_ignore_ascii_case("priority"));
+        self.header("priority", priority.as_str())
+    }
+
+    /// Sets the body and its `Content-Type`.
+    pub fn body(mut self, body: impl Into<Bytes>, content_type: impl Into<String>) -> Self {
+        self.body = Some((body.into(), content_type.into()));
+        self
+    }
+
+    /// The `sendevent` command firing this event.
+    pub fn command(&self) -> Result<Command<'static>, OutgoingEventError> {
+        self.validate()?;
+        let cmd = Command::sendevent(self.headers());
+        Ok(match &self.body {
+            Some((body, _)) => cmd.body(body.clone()),
+            None => cmd,
+        })
+    }
+
+    // Everything after `sendevent ` for `EventBuilder!`, which predates the
+    // checks and never fails: names are written as given. The body is text
+    // there, Content-Length counts the bytes actually written.
+    #[doc(hidden)]
+    pub fn to_text(&self) -> String {
+        let mut out = self.headers();
+        let body = self
+            .body
+            .as_ref()
+            .map(|(body, _)| String::from_utf8_lossy(body))
+            .unwrap_or_default();
+        if self.body.is_some() {
+            let _ = writeln!(out, "Content-Length: {}", body.len());
+        }
+        out.push('\n');
+        out.push_str(&body);
+        out
+    }
+
+    fn validate(&self) -> Result<(), OutgoingEventError> {
+        if self.name.is_empty() || self.name.contains(|c: char| c.is_whitespace()) {
+            return Err(OutgoingEventError::InvalidEventName(self.name.clone()));
+        }
+        match self.headers.iter().find(|(k, _)| !is_header_name(k)) {
+            Some((k, _)) => Err(OutgoingEventError::InvalidHeaderName(k.clone())),
+            None => Ok(()),
+        }
+    }
+
+    // Name and header lines, the codec writes Content-Length with the body
+    fn headers(&self) -> String {
+        let mut out = format!("{}\n", self.name);
+        for (k, v) in &self.headers {
+            let _ = writeln!(out, "{k}: {}", escape_line_breaks(v));
+        }
+        if let Some((_, content_type)) = &self.body {
+            let _ = writeln!(out, "Content-Type: {}", escape_line_breaks(content_type));
+        }
+        out
+    }
+}
+
+impl TryFrom<OutgoingEvent> for Command<'static> {
+    type Error = OutgoingEventError;
+
+    fn try_from(event: OutgoingEvent) -> Result<Self, Self::Error> {
+        event.command()
+    }
+}
+
+fn is_header_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.eq_ignore_ascii_case("Content-Length")
+        && name.bytes().all(|b| b.is_ascii_graphic() && b != b':')
+}
+
+/// Creates an event string to send over esl connection, shorthand for an
+/// [`OutgoingEvent`] with a text body.
+///
+/// Unlike [`OutgoingEvent::command`] names aren't checked, they are written
+/// as given. Use an [`OutgoingEvent`] to have them checked or to send a body
+/// that isn't text.
 ///
 /// # Examples
 /// ```
 /// use eslrs::EventBuilder;
 ///
-/// // EventBuilder will set content-type and length
+/// // EventBuilder will set Content-Type and Content-Length
 /// // headers for any suitable EventFormat
 /// let e = EventBuilder!(
 ///    "SEND_MESSAGE",
@@ -17,7 +214,7 @@
 ///    "profile" => "internal",
 ///    "user" => 100;
 ///
-///    "PLAIN TEXT BODY"     
+///    "PLAIN TEXT BODY"
 /// );
 ///
 /// let e = EventBuilder!(
@@ -40,26 +237,18 @@
 #[macro_export]
 macro_rules! EventBuilder {
     ($name:expr,$($k:expr=>$v:expr),* $(,)?) => {
-        format!("{}\n{}\n",$name, $crate::event::Header!($($k => $v),*))
-    };
-    ($name:expr, $($k:expr=>$v:expr),* $(,)? $( =>$rest:expr)?;$body:expr $(,$content:expr)?) => {
-        format!(
-            "{}\n{}{}\n{}",
-            $name,
-            $crate::event::Header!(
-                $($k => $v),*,
-                "content-length" => $body.len(),
-                "content-type" => $crate::EventBuilder!(@content $($content)?)
-            ),
-            $crate::EventBuilder!(@rest $($rest)?),
-            $body
-        )
-    };
-    (@rest $n:expr) => {
-        $n
+        $crate::event::OutgoingEvent::new($name)
+            $(.header($k, $v))*
+            .to_text()
     };
-    (@rest ) => {
-        ""
+    ($name:expr, $($k:expr=>$v:expr),* $(,)?;$body:expr $(,$content:expr)?) => {
+        $crate::event::OutgoingEvent::new($name)
+            $(.header($k, $v))*
+            .body(
+                ::std::string::ToString::to_string(&$body),
+                $crate::EventBuilder!(@content $($content)?),
+            )
+            .to_text()
     };
     (@content $n:expr) => {
         $n
@@ -86,3 +275,72 @@ macro_rules! Header {
 
 pub use EventBuilder;
 pub use Header;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EventName, codec::ESLCodec};
+    use bytes::BytesMut;
+
+    fn encoded(cmd: Command<'_>) -> BytesMut {
+        let mut dst = BytesMut::new();
+        ESLCodec::new().encode(cmd, &mut dst).unwrap();
+        dst
+    }
+
+    #[test]
+    fn test_outgoing_event() {
+        let event = OutgoingEvent::new(EventName::Notify)
+            .header("profile", "internal")
+            .header("user", 1000)
+            .priority(Priority::High)
+            .body(&b"\x00\xff"[..], "application/octet-stream");
+        assert_eq!(
+            encoded(event.command().unwrap()).as_ref(),
+            b"sendevent NOTIFY\nprofile: internal\nuser: 1000\npriority: HIGH\n\
+            Content-Type: application/octet-stream\nContent-Length: 2\n\n\x00\xff\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_outgoing_event_custom() {
+        let event = OutgoingEvent::new("HEARTBEAT")
+            .custom_subclass("myapp::old")
+            .custom_subclass("myapp::ready")
+            .header("Info", "two\r\nlines");
+        assert_eq!(
+            encoded(Command::try_from(event).unwrap()).as_ref(),
+            b"sendevent CUSTOM\nEvent-Subclass: myapp::ready\nInfo: two%0D%0Alines\n\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_outgoing_event_invalid() {
+        let invalid = |e: OutgoingEvent| e.command().unwrap_err();
+        assert_eq!(
+            invalid(OutgoingEvent::new("BAD NAME")),
+            OutgoingEventError::InvalidEventName("BAD NAME".to_string())
+        );
+        for name in ["", "Bad Name", "Bad:Name", "Bad\nName", "content-length"] {
+            assert_eq!(
+                invalid(OutgoingEvent::new("CUSTOM").header(name, "x")),
+                OutgoingEventError::InvalidHeaderName(name.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_event_builder_macro() {
+        let body = String::from("hello");
+        assert_eq!(
+            EventBuilder!("SEND_MESSAGE", "profile" => "internal", "user" => 100; body.as_str()),
+            "SEND_MESSAGE\nprofile: internal\nuser: 100\n\
+            Content-Type: text/plain\nContent-Length: 5\n\nhello"
+        );
+        // Written as given, line breaks in values are encoded
+        assert_eq!(
+            EventBuilder!("BAD NAME", "Bad Header" => "two\nlines"),
+            "BAD NAME\nBad Header: two%0Alines\n\n"
+        );
+    }
+}
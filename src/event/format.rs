@@ -1,7 +1,6 @@
-use crate::event::HeaderMap;
-use multimap::MultiMap;
+use crate::event::{HeaderMap, Headers};
+pub use bytes::Bytes;
 use std::convert::Infallible;
-pub use tokio_util::bytes::Bytes;
 
 pub trait EventFormat: Sized {
     const CONTENT_TYPE: &str;
@@ -33,6 +32,69 @@ impl PlainEvent {
     pub fn get_header(&self, header: &str) -> Option<&str> {
         self.0.get_header(header)
     }
+
+    /// All values of a repeated header, in the order they were received.
+    pub fn get_all(&self, header: &str) -> Vec<&str> {
+        self.0.get_all(header)
+    }
+
+    /// Iterates every header, ie. for logging or forwarding without JSON mode.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use eslrs::event::PlainEvent;
+    /// # fn example(event: PlainEvent) {
+    /// for (name, value) in event.headers() {
+    ///     println!("{name}: {value}");
+    /// }
+    /// # }
+    /// ```
+    pub fn headers(&self) -> Headers<'_> {
+        self.0.headers()
+    }
+
+    /// Converts to the object FreeSWITCH sends for json events, so both
+    /// formats can be handled, logged or stored the same way.
+    ///
+    /// Values are percent-decoded, `ARRAY::` values become arrays, the body is
+    /// kept as `_body` and `Content-Length` is dropped. Only the first of
+    /// repeated headers is kept.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use eslrs::event::{Event, JsonEvent, PlainEvent};
+    /// # fn example(event: Event) {
+    /// let json = match PlainEvent::try_from(event.clone()) {
+    ///     Ok(plain) if event.is_plain_event() => plain.to_json(),
+    ///     _ => JsonEvent::try_from(event).unwrap(),
+    /// };
+    /// println!("{}", json["Event-Name"]);
+    /// # }
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> JsonEvent {
+        let mut map = serde_json::Map::new();
+        for (name, value) in self.headers() {
+            if name.eq_ignore_ascii_case("Content-Length") || map.contains_key(name) {
+                continue;
+            }
+            let value = super::percent_decode(value);
+            let value = match value.starts_with("ARRAY::") {
+                true => super::parse_array(&value).into(),
+                false => value.into_owned().into(),
+            };
+            map.insert(name.to_string(), value);
+        }
+        if let Some(body) = self.get_body() {
+            map.insert(
+                "_body".to_string(),
+                String::from_utf8_lossy(body).into_owned().into(),
+            );
+        }
+        JsonEvent::Object(map)
+    }
 }
 
 impl EventFormat for PlainEvent {
@@ -40,36 +102,19 @@ impl EventFormat for PlainEvent {
     type Error = Infallible;
 
     fn try_from_raw(data: &Bytes) -> Result<Self, <Self as EventFormat>::Error> {
-        let mut map = MultiMap::new();
-
-        let mut last: usize = 0;
-        while data.len() > last
-            && let Some(n) = data[last..].iter().position(|c| *c == b'\n')
-        {
-            let line_end = last + n;
-            let line = data.slice(last..line_end);
-            if line.is_empty() {
-                last = line_end + 1;
-                break;
-            };
-            let split = line.iter().position(|c| *c == b':');
-            if let Some(i) = split {
-                let k = &line[..i];
-                let v = &line[i + 1..]; // Skip the colon
-                let b1 = data.slice_ref(k.trim_ascii());
-                let b2 = data.slice_ref(v.trim_ascii());
-                map.insert(b1, b2);
-            }
-            if data.len() > line_end + 1 {
-                last = line_end + 1;
-            } else {
-                break;
+        // Headers run until the first blank line, anything after is body
+        let mut pos = 0;
+        let (headers_end, body_start) = loop {
+            match data[pos..].iter().position(|c| *c == b'\n') {
+                Some(0) => break (pos, pos + 1),
+                Some(n) => pos += n + 1,
+                None => break (pos, pos),
             }
-        }
-        let body = data.clone().split_off(last);
+        };
+        let body = data.slice(body_start..);
 
         Ok(PlainEvent(
-            HeaderMap(map),
+            HeaderMap::parse(data.slice(..headers_end)),
             if body.is_empty() { None } else { Some(body) },
         ))
     }
@@ -80,8 +125,6 @@ mod tests {
     use super::*;
     use indoc::indoc;
 
-    // TODO: cover multi headers
-
     #[test]
     fn test_plain_event_parsing() {
         let raw_data = indoc! {b"
@@ -185,6 +228,84 @@ mod tests {
         assert_eq!(plain_event.get_header("NonExistent"), None);
     }
 
+    #[test]
+    fn test_plain_event_multi_headers() {
+        let raw_data = indoc! { b"
+        Event-Name: CUSTOM
+        Route: a
+        Route: b
+        \n"
+        };
+
+        let bytes = Bytes::from_static(raw_data);
+        let plain_event = PlainEvent::try_from_raw(&bytes).unwrap();
+
+        assert_eq!(plain_event.get_header("Route"), Some("a"));
+        assert_eq!(plain_event.get_all("route"), vec!["a", "b"]);
+        assert!(plain_event.get_all("Missing").is_empty());
+        assert_eq!(
+            plain_event.headers().collect::<Vec<_>>(),
+            vec![("Event-Name", "CUSTOM"), ("Route", "a"), ("Route", "b")]
+        );
+    }
+
+    #[test]
+    fn test_plain_event_invalid_utf8() {
+        let bytes = Bytes::from_static(b"Event-Name: HEARTBEAT\nBad: \xff\n\nbody");
+        let plain_event = PlainEvent::try_from_raw(&bytes).unwrap();
+
+        assert_eq!(plain_event.get_header("Event-Name"), Some("HEARTBEAT"));
+        assert_eq!(plain_event.get_header("Bad"), Some("INVALID UTF8"));
+        assert_eq!(plain_event.get_body().unwrap().as_ref(), b"body");
+    }
+
+    #[test]
+    fn test_plain_event_header_case() {
+        let raw_data = indoc! { b"
+        Event-Name: HEARTBEAT
+        Event-Name-Extra: nope
+        \n"
+        };
+
+        let bytes = Bytes::from_static(raw_data);
+        let plain_event = PlainEvent::try_from_raw(&bytes).unwrap();
+
+        assert_eq!(plain_event.get_header("event-name"), Some("HEARTBEAT"));
+        assert_eq!(plain_event.get_header("EVENT-NAME"), Some("HEARTBEAT"));
+        assert_eq!(plain_event.get_header("Event"), None);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_plain_event_to_json() {
+        let raw_data = indoc! { b"
+        Event-Name: CUSTOM
+        Event-Subclass: sms::recv
+        Event-Date-Local: 2008-05-02%2007%3A37%3A03
+        Route: a
+        Route: b
+        Codecs: ARRAY::PCMU%7C%3APCMA
+        Content-Length: 5
+
+        hello"
+        };
+
+        let bytes = Bytes::from_static(raw_data);
+        let plain_event = PlainEvent::try_from_raw(&bytes).unwrap();
+
+        assert_eq!(
+            plain_event.to_json(),
+            serde_json::json!({
+                "Event-Name": "CUSTOM",
+                "Event-Subclass": "sms::recv",
+                "Event-Date-Local": "2008-05-02 07:37:03",
+                "Route": "a",
+                "Codecs": ["PCMU", "PCMA"],
+                "_body": "hello",
+            })
+        );
+    }
+
     #[test]
     fn test_plain_event_whitespace_handling() {
         let raw_data = indoc! { b"
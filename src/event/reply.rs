@@ -1,8 +1,8 @@
-use tokio_util::bytes::Bytes;
+use bytes::Bytes;
 
 use crate::{
     ESLError,
-    event::{RawEvent, delegate},
+    event::{Headers, RawEvent, delegate},
 };
 
 #[derive(Clone, Debug)]
@@ -24,6 +24,8 @@ impl TryFrom<RawEvent> for Reply {
 
 impl Reply {
     delegate!(get_header (header: str) -> Option<&str> );
+    delegate!(get_all (header: str) -> Vec<&str> );
+    delegate!(headers () -> Headers<'_> );
     delegate!(get_body () -> Option<&Bytes> );
     delegate!(get_content_type() -> Option<&str> );
 
@@ -0,0 +1,93 @@
+use crate::{Command, EventName};
+
+/// A server side event filter, only events matching at least one
+/// active filter are sent once any filter is set.
+///
+/// # Examples
+///
+/// ```
+/// use eslrs::{EventName, Filter};
+///
+/// // filter Unique-ID 7f4db78a-17d7-11dd-b7a0-db4edd065621
+/// let call = Filter::unique_id("7f4db78a-17d7-11dd-b7a0-db4edd065621");
+///
+/// // filter Event-Name CHANNEL_HANGUP
+/// let hangups = Filter::event_name(EventName::ChannelHangup);
+///
+/// // filter Caller-Destination-Number /^9[0-9]{3}$/
+/// let extensions = Filter::regex("Caller-Destination-Number", "^9[0-9]{3}$");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// Header equals the value exactly
+    Value { header: String, value: String },
+    /// Header matches a PCRE pattern, evaluated by FreeSWITCH
+    Regex { header: String, pattern: String },
+}
+
+impl Filter {
+    pub fn new(header: impl Into<String>, value: impl Into<String>) -> Self {
+        Filter::Value {
+            header: header.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn regex(header: impl Into<String>, pattern: impl Into<String>) -> Self {
+        Filter::Regex {
+            header: header.into(),
+            pattern: pattern.into(),
+        }
+    }
+
+    /// Events belonging to a single channel.
+    pub fn unique_id(uuid: impl Into<String>) -> Self {
+        Filter::new("Unique-ID", uuid)
+    }
+
+    pub fn event_name(name: EventName) -> Self {
+        Filter::new("Event-Name", name.as_str())
+    }
+
+    pub fn header(&self) -> &str {
+        match self {
+            Filter::Value { header, .. } | Filter::Regex { header, .. } => header,
+        }
+    }
+
+    /// The `filter` command adding this filter.
+    pub fn command(&self) -> Command<'static> {
+        Command::filter(self.args())
+    }
+
+    /// The `filter delete` command removing this filter.
+    pub fn delete_command(&self) -> Command<'static> {
+        Command::filter_delete(self.args())
+    }
+
+    fn args(&self) -> String {
+        match self {
+            Filter::Value { header, value } => format!("{header} {value}"),
+            Filter::Regex { header, pattern } => format!("{header} /{pattern}/"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_commands() {
+        let cmd = Filter::unique_id("abc123").command();
+        assert_eq!(cmd.cmd, "filter ");
+        assert_eq!(cmd.args, "Unique-ID abc123");
+
+        let cmd = Filter::regex("Caller-Destination-Number", "^9[0-9]{3}$").delete_command();
+        assert_eq!(cmd.cmd, "filter delete ");
+        assert_eq!(cmd.args, "Caller-Destination-Number /^9[0-9]{3}$/");
+
+        let cmd = Filter::event_name(EventName::Heartbeat).command();
+        assert_eq!(cmd.args, "Event-Name HEARTBEAT");
+    }
+}
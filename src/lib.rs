@@ -1,11 +1,43 @@
-#![doc = include_str!("../README.md")]
+// The README examples need tokio and json events
+#![cfg_attr(
+    any(not(doctest), all(feature = "tokio", feature = "json")),
+    doc = include_str!("../README.md")
+)]
 mod api;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod codec;
 mod command;
 mod connection;
+mod connector;
 mod error;
 pub mod event;
+mod filter;
+mod matcher;
+#[cfg(feature = "record")]
+pub mod record;
+pub mod rt;
+mod subscription;
+#[cfg(any(all(test, feature = "tokio"), feature = "testing"))]
+pub mod testing;
+#[cfg(feature = "tls")]
+pub mod tls;
+mod url;
 
 pub use api::*;
 pub use command::Command;
-pub use connection::ESLConnection;
-pub use error::ESLError;
+pub use connection::{Drain, ESLConnection, ShutdownOptions, ShutdownReport};
+pub use connector::Connector;
+#[cfg(feature = "smol")]
+pub use connector::SmolConnector;
+#[cfg(feature = "tokio")]
+pub use connector::TcpConnector;
+#[cfg(all(unix, feature = "tokio"))]
+pub use connector::UnixConnector;
+pub use error::{ESLError, ErrorKind};
+pub use filter::Filter;
+pub use matcher::EventMatcher;
+pub use subscription::{EventName, EventSubscription, SubscriptionFormat, UnknownEventName};
+#[cfg(feature = "tokio")]
+pub use url::EndpointStream;
+pub use url::{ESLUrl, Endpoint, UrlError};
@@ -0,0 +1,197 @@
+use std::borrow::Cow;
+
+use crate::{
+    EventName,
+    event::{Event, PlainEvent, percent_decode},
+};
+
+/// Client side event predicate for
+/// [`ESLConnection::wait_for`](crate::ESLConnection::wait_for).
+///
+/// An event matches when every condition holds. Headers are read from the
+/// event body for plain and JSON events, percent-encoded plain values are
+/// decoded before comparing.
+///
+/// # Examples
+///
+/// ```
+/// use eslrs::{EventMatcher, EventName};
+///
+/// let answered = EventMatcher::new()
+///     .event_name(EventName::ChannelAnswer)
+///     .header("Unique-ID", "7f4db78a-17d7-11dd-b7a0-db4edd065621");
+///
+/// let registrations = EventMatcher::new().subclass("sofia::register");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EventMatcher {
+    conditions: Vec<Condition>,
+}
+
+// A header test, shared with the mock server's filters
+#[derive(Debug, Clone)]
+pub(crate) enum Condition {
+    Equals(String, String),
+    #[cfg(feature = "regex")]
+    Regex(String, regex::Regex),
+}
+
+impl Condition {
+    pub(crate) fn header(&self) -> &str {
+        match self {
+            Condition::Equals(name, _) => name,
+            #[cfg(feature = "regex")]
+            Condition::Regex(name, _) => name,
+        }
+    }
+
+    pub(crate) fn is_match(&self, value: &str) -> bool {
+        match self {
+            Condition::Equals(_, expected) => value == expected,
+            #[cfg(feature = "regex")]
+            Condition::Regex(_, pattern) => pattern.is_match(value),
+        }
+    }
+}
+
+impl EventMatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn event_name(self, name: EventName) -> Self {
+        self.header("Event-Name", name.as_str())
+    }
+
+    /// `CUSTOM` events of the given subclass, ie. `sofia::register`.
+    pub fn subclass(self, subclass: impl Into<String>) -> Self {
+        self.header("Event-Name", "CUSTOM")
+            .header("Event-Subclass", subclass)
+    }
+
+    /// Events of a single channel.
+    pub fn unique_id(self, uuid: impl Into<String>) -> Self {
+        self.header("Unique-ID", uuid)
+    }
+
+    /// Header equals `value` exactly.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.conditions
+            .push(Condition::Equals(name.into(), value.into()));
+        self
+    }
+
+    /// Header matches `pattern`.
+    #[cfg(feature = "regex")]
+    pub fn header_regex(mut self, name: impl Into<String>, pattern: regex::Regex) -> Self {
+        self.conditions.push(Condition::Regex(name.into(), pattern));
+        self
+    }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        let fields = Fields::of(event);
+        self.conditions.iter().all(|c| {
+            fields
+                .get(event, c.header())
+                .is_some_and(|v| c.is_match(&v))
+        })
+    }
+}
+
+// Where an event keeps its headers, depends on the subscription format
+enum Fields {
+    Frame,
+    Plain(PlainEvent),
+    #[cfg(feature = "json")]
+    Json(crate::event::JsonEvent),
+}
+
+impl Fields {
+    fn of(event: &Event) -> Self {
+        if event.is_plain_event() {
+            return PlainEvent::try_from(event.clone())
+                .map(Fields::Plain)
+                .unwrap_or(Fields::Frame);
+        }
+        #[cfg(feature = "json")]
+        if event.is_json() {
+            return crate::event::JsonEvent::try_from(event.clone())
+                .map(Fields::Json)
+                .unwrap_or(Fields::Frame);
+        }
+        Fields::Frame
+    }
+
+    fn get<'a>(&'a self, event: &'a Event, name: &str) -> Option<Cow<'a, str>> {
+        match self {
+            Fields::Frame => event.get_header(name).map(Cow::Borrowed),
+            Fields::Plain(plain) => plain.get_header(name).map(percent_decode),
+            #[cfg(feature = "json")]
+            Fields::Json(json) => {
+                let (_, v) = json
+                    .as_object()?
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case(name))?;
+                match v {
+                    serde_json::Value::String(s) => Some(Cow::Borrowed(s)),
+                    v => Some(Cow::Owned(v.to_string())),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::ESLCodec;
+    use bytes::BytesMut;
+
+    fn plain_event(body: &str) -> Event {
+        let frame = format!(
+            "Content-Length: {}\nContent-Type: text/event-plain\n\n{body}",
+            body.len()
+        );
+        ESLCodec::new()
+            .decode(&mut BytesMut::from(frame.as_str()))
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_matcher_plain_event() {
+        let event = plain_event(
+            "Event-Name: CUSTOM\nEvent-Subclass: sofia%3A%3Aregister\nUnique-ID: abc123\n\n",
+        );
+
+        assert!(
+            EventMatcher::new()
+                .subclass("sofia::register")
+                .matches(&event)
+        );
+        assert!(EventMatcher::new().unique_id("abc123").matches(&event));
+        assert!(
+            !EventMatcher::new()
+                .subclass("sofia::register")
+                .unique_id("other")
+                .matches(&event)
+        );
+        assert!(
+            !EventMatcher::new()
+                .event_name(EventName::Heartbeat)
+                .matches(&event)
+        );
+        assert!(EventMatcher::new().matches(&event));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_matcher_regex() {
+        let event = plain_event("Event-Name: CHANNEL_ANSWER\nCaller-Destination-Number: 9196\n\n");
+        let matcher = EventMatcher::new().header_regex(
+            "Caller-Destination-Number",
+            regex::Regex::new("^9[0-9]{3}$").unwrap(),
+        );
+        assert!(matcher.matches(&event));
+    }
+}
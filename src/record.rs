@@ -0,0 +1,582 @@
+//! Capture and replay of event socket traffic.
+//!
+//! Wrap the stream given to [`ESLConnection`](crate::ESLConnection) in a
+//! [`Recorder`] to log every frame received from FreeSWITCH and every command
+//! sent, as JSON lines:
+//!
+//! ```text
+//! {"elapsed_us":0,"dir":"in","data":"Content-Type: auth/request\n\n"}
+//! {"elapsed_us":412,"dir":"out","data":"auth ClueCon\r\n\r\n"}
+//! ```
+//!
+//! Chunks that aren't valid UTF-8, ie. binary bodies, are stored base64
+//! encoded under `data_base64` instead, so replay gets back the exact bytes.
+//!
+//! A [`Replay`] feeds a recording back to a connection in place of the
+//! network, ie. to reproduce a production bug in a test. Recorded frames are
+//! delivered in order, frames recorded after a command are held back until
+//! the client sends a command of its own. Timing is not reproduced.
+//!
+//! Enable the `record` feature to use this module.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use eslrs::record::{Recorder, Replay};
+//! use eslrs::Inbound;
+//! use tokio::net::TcpStream;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let stream = TcpStream::connect("0.0.0.0:8021").await.unwrap();
+//! let stream = Recorder::create(stream, "session.jsonl").unwrap();
+//! let conn = Inbound::handshake(stream, "ClueCon").await.unwrap();
+//!
+//! // Later, in a test
+//! let replay = Replay::open("session.jsonl").unwrap();
+//! let conn = Inbound::handshake(replay, "ClueCon").await.unwrap();
+//! # }
+//! ```
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use bytes::BytesMut;
+use pin_project_lite::pin_project;
+use serde_json::{Value, json};
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufRead, BufReader, LineWriter, Write},
+    path::Path,
+    pin::Pin,
+    sync::mpsc,
+    task::{Context, Poll, Waker},
+    thread,
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    time::Instant,
+};
+
+use crate::codec::ESLCodec;
+
+/// Which way a recorded chunk travelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Received from FreeSWITCH
+    In,
+    /// Sent to FreeSWITCH
+    Out,
+}
+
+/// A single entry of a recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// Time since the recording started
+    pub elapsed: Duration,
+    pub direction: Direction,
+    pub data: Vec<u8>,
+}
+
+impl Record {
+    fn to_json(&self) -> String {
+        let mut record = json!({
+            "elapsed_us": self.elapsed.as_micros() as u64,
+            "dir": match self.direction {
+                Direction::In => "in",
+                Direction::Out => "out",
+            },
+        });
+        match std::str::from_utf8(&self.data) {
+            Ok(data) => record["data"] = data.into(),
+            Err(_) => record["data_base64"] = BASE64.encode(&self.data).into(),
+        }
+        record.to_string()
+    }
+
+    fn from_json(line: &str) -> io::Result<Self> {
+        let invalid = |reason: &str| io::Error::new(io::ErrorKind::InvalidData, reason.to_string());
+        let v: Value = serde_json::from_str(line)?;
+        let direction = match v["dir"].as_str() {
+            Some("in") => Direction::In,
+            Some("out") => Direction::Out,
+            _ => return Err(invalid("record has no valid dir")),
+        };
+        let data = match (v["data"].as_str(), v["data_base64"].as_str()) {
+            (Some(data), _) => data.as_bytes().to_vec(),
+            (None, Some(data)) => BASE64
+                .decode(data)
+                .map_err(|_| invalid("record has invalid data_base64"))?,
+            (None, None) => return Err(invalid("record has no data")),
+        };
+        Ok(Record {
+            elapsed: Duration::from_micros(v["elapsed_us"].as_u64().unwrap_or_default()),
+            direction,
+            data,
+        })
+    }
+}
+
+pin_project! {
+    /// Stream wrapper logging traffic as JSON lines, see the [module docs](self).
+    ///
+    /// Received bytes are logged once they form a complete frame, sent bytes
+    /// once they form a complete command. Entries are written to the log by a
+    /// background thread, so a slow log never blocks the connection, and
+    /// failing to write it never fails the connection. Dropping the recorder
+    /// waits for the pending entries to be written.
+    pub struct Recorder<S> {
+        #[pin]
+        inner: S,
+        log: LogWriter,
+        start: Instant,
+        codec: Option<ESLCodec>,
+        // Mirror of the bytes the codec is framing
+        pending: BytesMut,
+        raw: Vec<u8>,
+        // Bytes of `raw` the codec consumed for the frame in progress,
+        // the header is consumed before its body has arrived
+        consumed: usize,
+        // Sent bytes not yet forming a whole command
+        sent: Vec<u8>,
+    }
+}
+
+impl<S> Recorder<S> {
+    pub fn new(inner: S, log: impl Write + Send + 'static) -> Self {
+        Self {
+            inner,
+            log: LogWriter::spawn(log),
+            start: Instant::now(),
+            codec: Some(ESLCodec::new()),
+            pending: BytesMut::new(),
+            raw: vec![],
+            consumed: 0,
+            sent: vec![],
+        }
+    }
+
+    /// Records to a new file at `path`, flushed after every entry.
+    pub fn create(inner: S, path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(inner, LineWriter::new(File::create(path)?)))
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+// Hands log lines to a thread doing the blocking writes, joined on drop
+struct LogWriter {
+    lines: Option<mpsc::Sender<String>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl LogWriter {
+    fn spawn(mut log: impl Write + Send + 'static) -> Self {
+        let (tx, rx) = mpsc::channel::<String>();
+        let thread = thread::spawn(move || {
+            for line in rx {
+                if let Err(_e) = writeln!(log, "{line}") {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(error = %_e, "failed to write session recording");
+                }
+            }
+            let _ = log.flush();
+        });
+        Self {
+            lines: Some(tx),
+            thread: Some(thread),
+        }
+    }
+
+    fn record(&self, start: Instant, direction: Direction, data: &[u8]) {
+        let record = Record {
+            elapsed: start.elapsed(),
+            direction,
+            data: data.to_vec(),
+        };
+        if let Some(lines) = &self.lines {
+            let _ = lines.send(record.to_json());
+        }
+    }
+}
+
+impl Drop for LogWriter {
+    fn drop(&mut self) {
+        drop(self.lines.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// Length of the first whole command in `buf` as `ESLCodec::encode` writes
+// it: up to END, or the header block, body and END with a Content-Length
+fn command_len(buf: &[u8]) -> Option<usize> {
+    const END: &[u8] = b"\r\n\r\n";
+    let find = |needle: &[u8]| buf.windows(needle.len()).position(|w| w == needle);
+    let end = find(END);
+    if let Some(head) = find(b"\n\n").filter(|head| end.is_none_or(|end| *head < end)) {
+        let body_len = buf[..head]
+            .split(|c| *c == b'\n')
+            .filter_map(|line| str::from_utf8(line).ok()?.split_once(':'))
+            .find(|(k, _)| k.trim().eq_ignore_ascii_case("Content-Length"))
+            .and_then(|(_, v)| v.trim().parse::<usize>().ok());
+        if let Some(body_len) = body_len {
+            let len = head + 2 + body_len + END.len();
+            return (buf.len() >= len).then_some(len);
+        }
+    }
+    end.map(|end| end + END.len())
+}
+
+impl<S: AsyncRead> AsyncRead for Recorder<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.project();
+        let before = buf.filled().len();
+        std::task::ready!(this.inner.poll_read(cx, buf))?;
+        let received = &buf.filled()[before..];
+        if received.is_empty() {
+            return Poll::Ready(Ok(()));
+        }
+
+        let Some(codec) = this.codec else {
+            this.log.record(*this.start, Direction::In, received);
+            return Poll::Ready(Ok(()));
+        };
+        this.pending.extend_from_slice(received);
+        this.raw.extend_from_slice(received);
+        loop {
+            let len = this.pending.len();
+            let res = codec.decode(this.pending);
+            *this.consumed += len - this.pending.len();
+            match res {
+                Ok(Some(_)) => {
+                    let frame: Vec<u8> = this.raw.drain(..*this.consumed).collect();
+                    *this.consumed = 0;
+                    this.log.record(*this.start, Direction::In, &frame);
+                }
+                Ok(None) => break,
+                Err(_) => {
+                    // Can't frame it, log whatever arrives from now on
+                    this.log.record(*this.start, Direction::In, this.raw);
+                    this.raw.clear();
+                    *this.consumed = 0;
+                    *this.codec = None;
+                    break;
+                }
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: AsyncWrite> AsyncWrite for Recorder<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let n = std::task::ready!(this.inner.poll_write(cx, buf))?;
+        this.sent.extend_from_slice(&buf[..n]);
+        while let Some(len) = command_len(this.sent) {
+            let command: Vec<u8> = this.sent.drain(..len).collect();
+            this.log.record(*this.start, Direction::Out, &command);
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.project();
+        // Bytes that never formed a command are still worth keeping
+        if !this.sent.is_empty() {
+            this.log.record(*this.start, Direction::Out, this.sent);
+            this.sent.clear();
+        }
+        this.inner.poll_shutdown(cx)
+    }
+}
+
+/// Transport playing back a [`Recorder`] log, see the [module docs](self).
+pub struct Replay {
+    records: VecDeque<Record>,
+    // Bytes of the front record already read
+    offset: usize,
+    reader: Option<Waker>,
+    strict: bool,
+}
+
+impl Replay {
+    pub fn new(records: impl IntoIterator<Item = Record>) -> Self {
+        Self {
+            records: records.into_iter().collect(),
+            offset: 0,
+            reader: None,
+            strict: false,
+        }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_reader(reader: impl BufRead) -> io::Result<Self> {
+        let mut records = vec![];
+        for line in reader.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                records.push(Record::from_json(&line)?);
+            }
+        }
+        Ok(Self::new(records))
+    }
+
+    /// Fail writes that differ from the recorded commands with
+    /// [`io::ErrorKind::InvalidData`], rather than accepting any command.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Records not yet played back.
+    pub fn remaining(&self) -> impl Iterator<Item = &Record> {
+        self.records.iter()
+    }
+}
+
+impl AsyncRead for Replay {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let offset = self.offset;
+        let Some(record) = self.records.front() else {
+            return Poll::Ready(Ok(()));
+        };
+        if record.direction == Direction::Out {
+            // Wait for the client to send the recorded command
+            self.reader = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        let data = &record.data[offset..];
+        let n = data.len().min(buf.remaining());
+        buf.put_slice(&data[..n]);
+        if offset + n == record.data.len() {
+            self.records.pop_front();
+            self.offset = 0;
+        } else {
+            self.offset += n;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for Replay {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        // Frames recorded before the command may not have been read yet
+        let Some(i) = this
+            .records
+            .iter()
+            .position(|r| r.direction == Direction::Out)
+        else {
+            return match this.strict {
+                true => Poll::Ready(Err(mismatch(buf, b""))),
+                false => Poll::Ready(Ok(buf.len())),
+            };
+        };
+        let offset = if i == 0 { this.offset } else { 0 };
+        let expected = &this.records[i].data[offset..];
+        let n = expected.len().min(buf.len());
+        if this.strict && expected[..n] != buf[..n] {
+            return Poll::Ready(Err(mismatch(buf, expected)));
+        }
+        if n == expected.len() {
+            this.records.remove(i);
+            if i == 0 {
+                this.offset = 0;
+            }
+            if let Some(w) = this.reader.take() {
+                w.wake();
+            }
+        } else if i == 0 {
+            this.offset += n;
+        } else {
+            // Partially written command, track progress at the front
+            this.records[i].data.drain(..n);
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn mismatch(written: &[u8], expected: &[u8]) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "replay expected {:?}, got {:?}",
+            String::from_utf8_lossy(expected),
+            String::from_utf8_lossy(written)
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Command, Inbound};
+    use std::sync::{Arc, Mutex};
+    use tokio_test::io::Builder;
+
+    const SESSION: &str = include_str!("../tests/data/freeswitch_session.jsonl");
+
+    #[derive(Clone, Default)]
+    struct SharedLog(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedLog {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recorder_logs_frames_and_commands() {
+        let stream = Builder::new()
+            // Greeting split across reads is logged as one frame
+            .read(b"Content-Type: auth/")
+            .read(b"request\n\n")
+            // As is the command split across writes
+            .write(b"auth Clue")
+            .write(b"Con\r\n\r\n")
+            .read(b"Content-Type: command/reply\nReply-Text: +OK accepted\n\n")
+            .build();
+        let log = SharedLog::default();
+        Inbound::handshake(Recorder::new(stream, log.clone()), "ClueCon")
+            .await
+            .unwrap();
+
+        let log = String::from_utf8(log.0.lock().unwrap().clone()).unwrap();
+        let records: Vec<_> = log.lines().map(|l| Record::from_json(l).unwrap()).collect();
+        let entries: Vec<_> = records
+            .iter()
+            .map(|r| (r.direction, String::from_utf8(r.data.clone()).unwrap()))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                (Direction::In, "Content-Type: auth/request\n\n".to_string()),
+                (Direction::Out, "auth ClueCon\r\n\r\n".to_string()),
+                (
+                    Direction::In,
+                    "Content-Type: command/reply\nReply-Text: +OK accepted\n\n".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recorder_body_split_from_header() {
+        let stream = Builder::new()
+            .read(b"Content-Type: api/response\nContent-Length: 5\n\n")
+            .read(b"hello")
+            .read(b"Content-Type: text/event-plain\nContent-Length: 2\n\n\x00\xff")
+            .build();
+        let log = SharedLog::default();
+        let mut recorder = Recorder::new(stream, log.clone());
+        let mut received = vec![];
+        tokio::io::AsyncReadExt::read_to_end(&mut recorder, &mut received)
+            .await
+            .unwrap();
+        drop(recorder);
+
+        let log = String::from_utf8(log.0.lock().unwrap().clone()).unwrap();
+        let records: Vec<_> = log.lines().map(|l| Record::from_json(l).unwrap()).collect();
+        let frames: Vec<_> = records.iter().map(|r| r.data.as_slice()).collect();
+        assert_eq!(
+            frames,
+            [
+                &b"Content-Type: api/response\nContent-Length: 5\n\nhello"[..],
+                &b"Content-Type: text/event-plain\nContent-Length: 2\n\n\x00\xff"[..],
+            ]
+        );
+        assert!(log.lines().nth(1).unwrap().contains("\"data_base64\""));
+
+        let mut replayed = vec![];
+        tokio::io::AsyncReadExt::read_to_end(&mut Replay::new(records), &mut replayed)
+            .await
+            .unwrap();
+        assert_eq!(replayed, received);
+    }
+
+    #[test]
+    fn test_command_len() {
+        assert_eq!(command_len(b"api status\r\n"), None);
+        assert_eq!(command_len(b"api status\r\n\r\napi"), Some(14));
+        // Blank line without a body, ie. from `EventBuilder!`
+        assert_eq!(command_len(b"sendevent X\na: 1\n\n\r\n\r\n"), Some(22));
+        let body = b"sendevent X\nContent-Length: 4\n\n\r\n\r\n\r\n\r\n";
+        assert_eq!(command_len(&body[..34]), None);
+        assert_eq!(command_len(body), Some(body.len()));
+    }
+
+    #[tokio::test]
+    async fn test_replay_session() {
+        let replay = Replay::from_reader(SESSION.as_bytes())
+            .unwrap()
+            .strict(true);
+        let mut conn = Inbound::handshake(replay, "ClueCon").await.unwrap();
+
+        let reply = conn.send_recv(Command::api("status")).await.unwrap();
+        assert!(reply.get_body().unwrap().starts_with(b"UP 0 years"));
+        conn.send_recv(Command::events("CHANNEL_ANSWER HEARTBEAT"))
+            .await
+            .unwrap();
+
+        let event = conn.recv().await.unwrap();
+        let event = crate::event::PlainEvent::try_from(event).unwrap();
+        assert_eq!(event.get_header("Event-Name"), Some("HEARTBEAT"));
+        let event = conn.recv().await.unwrap();
+        let event = crate::event::PlainEvent::try_from(event).unwrap();
+        assert_eq!(event.get_header("Event-Name"), Some("CHANNEL_ANSWER"));
+
+        let report = conn.shutdown(Default::default()).await;
+        assert!(report.result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_replay_strict_mismatch() {
+        let replay = Replay::from_reader(SESSION.as_bytes())
+            .unwrap()
+            .strict(true);
+        let res = Inbound::handshake(replay, "wrong").await;
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,356 @@
+//! Async runtime integration.
+//!
+//! The protocol core (codec, events and [`ESLConnection`](crate::ESLConnection))
+//! only needs a way to read and write the stream and a timer, so it isn't tied
+//! to a particular executor. A [`Runtime`] provides the timer and
+//! [`Transport`] connects a stream type to a runtime's IO traits.
+//!
+//! Two runtimes are built in:
+//!
+//! * [`Tokio`] via the default `tokio` feature, for any stream implementing
+//!   [`tokio::io::AsyncRead`] and [`tokio::io::AsyncWrite`]
+//! * [`Smol`] via the `smol` feature, for any stream implementing the
+//!   [`futures_io`] traits, with timers from `async-io`
+//!
+//! The runtime is picked from the stream type, so `ESLConnection::new(stream)`
+//! works the same way for both. Other executors implement [`Runtime`] and
+//! [`Transport`] for their own stream type.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # #[cfg(feature = "smol")]
+//! # fn main() {
+//! use eslrs::{Command, Inbound, SmolConnector};
+//!
+//! async_io::block_on(async {
+//!     let connector = SmolConnector::new("127.0.0.1:8021");
+//!     let mut conn = Inbound::connect_with(connector, "ClueCon").await.unwrap();
+//!     let reply = conn.send_recv(Command::api("status")).await.unwrap();
+//! });
+//! # }
+//! # #[cfg(not(feature = "smol"))]
+//! # fn main() {}
+//! ```
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use futures_util::future::{Either, select};
+
+/// Timer of an async runtime.
+pub trait Runtime {
+    type Sleep: Future<Output = ()> + Send + 'static;
+
+    /// Current time on the runtime's clock, ie. tokio's paused clock in tests.
+    fn now() -> Instant;
+
+    fn sleep_until(deadline: Instant) -> Self::Sleep;
+
+    /// Moves a pending sleep to a new deadline, replaces it by default.
+    fn reset(mut sleep: Pin<&mut Self::Sleep>, deadline: Instant) {
+        sleep.set(Self::sleep_until(deadline));
+    }
+}
+
+/// A stream usable with the runtime `R`.
+///
+/// Implemented for every stream with the IO traits of a built-in runtime.
+pub trait Transport<R: Runtime> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>>;
+
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>>;
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>>;
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>>;
+}
+
+pub(crate) type Sleep = Pin<Box<dyn Timer>>;
+type ReadFn<S> = fn(Pin<&mut S>, &mut Context<'_>, &mut [u8]) -> Poll<io::Result<usize>>;
+type WriteFn<S> = fn(Pin<&mut S>, &mut Context<'_>, &[u8]) -> Poll<io::Result<usize>>;
+type PollFn<S> = fn(Pin<&mut S>, &mut Context<'_>) -> Poll<io::Result<()>>;
+
+/// A stream's runtime captured when a connection is created,
+/// so connection types only carry the stream type.
+pub(crate) struct Driver<S> {
+    pub(crate) now: fn() -> Instant,
+    sleep_until: fn(Instant) -> Sleep,
+    pub(crate) poll_read: ReadFn<S>,
+    pub(crate) poll_write: WriteFn<S>,
+    pub(crate) poll_flush: PollFn<S>,
+    pub(crate) poll_close: PollFn<S>,
+}
+
+/// A runtime's sleep behind the driver's type erasure.
+pub(crate) trait Timer: Future<Output = ()> + Send {
+    fn reset(self: Pin<&mut Self>, deadline: Instant);
+}
+
+struct RuntimeSleep<T> {
+    sleep: T,
+    reset: fn(Pin<&mut T>, Instant),
+}
+
+impl<T> RuntimeSleep<T> {
+    fn sleep(self: Pin<&mut Self>) -> Pin<&mut T> {
+        // SAFETY: `sleep` is structurally pinned, it is never moved out and
+        // `RuntimeSleep` has no Drop impl.
+        unsafe { self.map_unchecked_mut(|s| &mut s.sleep) }
+    }
+}
+
+impl<T: Future<Output = ()>> Future for RuntimeSleep<T> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.sleep().poll(cx)
+    }
+}
+
+impl<T: Future<Output = ()> + Send> Timer for RuntimeSleep<T> {
+    fn reset(self: Pin<&mut Self>, deadline: Instant) {
+        let reset = self.reset;
+        reset(self.sleep(), deadline);
+    }
+}
+
+impl<S> Clone for Driver<S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<S> Copy for Driver<S> {}
+
+impl<S> Driver<S> {
+    pub(crate) fn new<R>() -> Self
+    where
+        R: Runtime,
+        S: Transport<R>,
+    {
+        Self {
+            now: R::now,
+            sleep_until: |deadline| {
+                Box::pin(RuntimeSleep {
+                    sleep: R::sleep_until(deadline),
+                    reset: R::reset,
+                })
+            },
+            poll_read: <S as Transport<R>>::poll_read,
+            poll_write: <S as Transport<R>>::poll_write,
+            poll_flush: <S as Transport<R>>::poll_flush,
+            poll_close: <S as Transport<R>>::poll_close,
+        }
+    }
+
+    pub(crate) fn sleep_until(&self, deadline: Instant) -> Sleep {
+        (self.sleep_until)(deadline)
+    }
+
+    /// Runs `fut` for at most `limit`, `None` if it elapsed.
+    pub(crate) async fn timeout<F: Future>(
+        &self,
+        limit: std::time::Duration,
+        fut: F,
+    ) -> Option<F::Output> {
+        let mut sleep = self.sleep_until((self.now)() + limit);
+        timeout(sleep.as_mut(), fut).await
+    }
+}
+
+pub(crate) async fn timeout<F: Future>(sleep: Pin<&mut dyn Timer>, fut: F) -> Option<F::Output> {
+    match select(std::pin::pin!(fut), sleep).await {
+        Either::Left((out, _)) => Some(out),
+        Either::Right(_) => None,
+    }
+}
+
+/// The tokio runtime, requires the `tokio` feature.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy)]
+pub struct Tokio;
+
+#[cfg(feature = "tokio")]
+impl Runtime for Tokio {
+    type Sleep = tokio::time::Sleep;
+
+    fn now() -> Instant {
+        tokio::time::Instant::now().into_std()
+    }
+
+    fn sleep_until(deadline: Instant) -> Self::Sleep {
+        tokio::time::sleep_until(deadline.into())
+    }
+
+    fn reset(sleep: Pin<&mut Self::Sleep>, deadline: Instant) {
+        sleep.reset(deadline.into());
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<S> Transport<Tokio> for S
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut buf = tokio::io::ReadBuf::new(buf);
+        std::task::ready!(tokio::io::AsyncRead::poll_read(self, cx, &mut buf))?;
+        Poll::Ready(Ok(buf.filled().len()))
+    }
+
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        tokio::io::AsyncWrite::poll_write(self, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        tokio::io::AsyncWrite::poll_flush(self, cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        tokio::io::AsyncWrite::poll_shutdown(self, cx)
+    }
+}
+
+/// smol, or any executor with `async-io` timers, requires the `smol` feature.
+#[cfg(feature = "smol")]
+#[derive(Debug, Clone, Copy)]
+pub struct Smol;
+
+#[cfg(feature = "smol")]
+impl Runtime for Smol {
+    type Sleep = SmolSleep;
+
+    fn now() -> Instant {
+        Instant::now()
+    }
+
+    fn sleep_until(deadline: Instant) -> Self::Sleep {
+        SmolSleep(async_io::Timer::at(deadline))
+    }
+
+    fn reset(mut sleep: Pin<&mut Self::Sleep>, deadline: Instant) {
+        sleep.0.set_at(deadline);
+    }
+}
+
+/// Future returned by [`Smol::sleep_until`](Runtime::sleep_until).
+#[cfg(feature = "smol")]
+#[derive(Debug)]
+pub struct SmolSleep(async_io::Timer);
+
+#[cfg(feature = "smol")]
+impl Future for SmolSleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        Pin::new(&mut self.0).poll(cx).map(|_| ())
+    }
+}
+
+#[cfg(feature = "smol")]
+impl<S> Transport<Smol> for S
+where
+    S: futures_io::AsyncRead + futures_io::AsyncWrite,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        futures_io::AsyncRead::poll_read(self, cx, buf)
+    }
+
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        futures_io::AsyncWrite::poll_write(self, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        futures_io::AsyncWrite::poll_flush(self, cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        futures_io::AsyncWrite::poll_close(self, cx)
+    }
+}
+
+#[cfg(all(test, feature = "smol"))]
+mod tests {
+    use crate::{Command, ErrorKind, Inbound, SmolConnector};
+    use futures_util::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, future::join, io::BufReader};
+    use std::time::Duration;
+
+    #[test]
+    fn test_smol_inbound() {
+        async_io::block_on(async {
+            let listener = async_net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+
+            let server = async {
+                let (socket, _) = listener.accept().await.unwrap();
+                let mut socket = BufReader::new(socket);
+                let command = async |socket: &mut BufReader<_>| {
+                    let mut line = String::new();
+                    socket.read_line(&mut line).await.unwrap();
+                    socket.read_line(&mut String::new()).await.unwrap();
+                    line.trim().to_string()
+                };
+                socket
+                    .write_all(b"Content-Type: auth/request\n\n")
+                    .await
+                    .unwrap();
+                assert_eq!(command(&mut socket).await, "auth ClueCon");
+                socket
+                    .write_all(b"Content-Type: command/reply\nReply-Text: +OK accepted\n\n")
+                    .await
+                    .unwrap();
+                assert_eq!(command(&mut socket).await, "api status");
+                socket
+                    .write_all(b"Content-Type: api/response\nContent-Length: 3\n\nUP\n")
+                    .await
+                    .unwrap();
+                // never answer, until the client hangs up
+                let _ = socket.read_to_end(&mut vec![]).await;
+            };
+
+            let client = async {
+                let mut conn = Inbound::connect_with(SmolConnector::new(addr), "ClueCon")
+                    .await
+                    .unwrap();
+                let reply = conn.send_recv(Command::api("status")).await.unwrap();
+                assert_eq!(reply.get_body().unwrap().as_ref(), b"UP\n");
+
+                let err = conn
+                    .send_recv_timeout(Command::api("version"), Duration::from_millis(50))
+                    .await
+                    .unwrap_err();
+                assert_eq!(err.kind(), &ErrorKind::Timeout);
+            };
+
+            join(server, client).await;
+        });
+    }
+}
@@ -0,0 +1,306 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use crate::Command;
+
+macro_rules! event_names {
+    ($($variant:ident => $name:literal),* $(,)?) => {
+        /// FreeSWITCH event types, as understood by `event` and `nixevent`.
+        ///
+        /// `CUSTOM` events are subscribed by subclass with
+        /// [`EventSubscription::custom`] instead.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum EventName {
+            $($variant),*
+        }
+
+        impl EventName {
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $(EventName::$variant => $name),*
+                }
+            }
+        }
+
+        impl FromStr for EventName {
+            type Err = UnknownEventName;
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($name => Ok(EventName::$variant),)*
+                    _ => Err(UnknownEventName(s.to_string())),
+                }
+            }
+        }
+    };
+}
+
+event_names! {
+    All => "ALL",
+    Api => "API",
+    BackgroundJob => "BACKGROUND_JOB",
+    CallDetail => "CALL_DETAIL",
+    CallSecure => "CALL_SECURE",
+    CallSetupReq => "CALL_SETUP_REQ",
+    CallSetupResult => "CALL_SETUP_RESULT",
+    CallUpdate => "CALL_UPDATE",
+    ChannelAnswer => "CHANNEL_ANSWER",
+    ChannelApplication => "CHANNEL_APPLICATION",
+    ChannelBridge => "CHANNEL_BRIDGE",
+    ChannelCallstate => "CHANNEL_CALLSTATE",
+    ChannelCreate => "CHANNEL_CREATE",
+    ChannelData => "CHANNEL_DATA",
+    ChannelDestroy => "CHANNEL_DESTROY",
+    ChannelExecute => "CHANNEL_EXECUTE",
+    ChannelExecuteComplete => "CHANNEL_EXECUTE_COMPLETE",
+    ChannelHangup => "CHANNEL_HANGUP",
+    ChannelHangupComplete => "CHANNEL_HANGUP_COMPLETE",
+    ChannelHold => "CHANNEL_HOLD",
+    ChannelOriginate => "CHANNEL_ORIGINATE",
+    ChannelOutgoing => "CHANNEL_OUTGOING",
+    ChannelPark => "CHANNEL_PARK",
+    ChannelProgress => "CHANNEL_PROGRESS",
+    ChannelProgressMedia => "CHANNEL_PROGRESS_MEDIA",
+    ChannelState => "CHANNEL_STATE",
+    ChannelUnbridge => "CHANNEL_UNBRIDGE",
+    ChannelUnhold => "CHANNEL_UNHOLD",
+    ChannelUnpark => "CHANNEL_UNPARK",
+    ChannelUuid => "CHANNEL_UUID",
+    Codec => "CODEC",
+    Command => "COMMAND",
+    ConferenceData => "CONFERENCE_DATA",
+    DelSchedule => "DEL_SCHEDULE",
+    DetectedSpeech => "DETECTED_SPEECH",
+    DetectedTone => "DETECTED_TONE",
+    DeviceState => "DEVICE_STATE",
+    Dtmf => "DTMF",
+    ExeSchedule => "EXE_SCHEDULE",
+    Failure => "FAILURE",
+    General => "GENERAL",
+    Heartbeat => "HEARTBEAT",
+    Log => "LOG",
+    MediaBugStart => "MEDIA_BUG_START",
+    MediaBugStop => "MEDIA_BUG_STOP",
+    Message => "MESSAGE",
+    MessageQuery => "MESSAGE_QUERY",
+    MessageWaiting => "MESSAGE_WAITING",
+    ModuleLoad => "MODULE_LOAD",
+    ModuleUnload => "MODULE_UNLOAD",
+    Nat => "NAT",
+    Notify => "NOTIFY",
+    NotifyIn => "NOTIFY_IN",
+    NoTalk => "NOTALK",
+    PhoneFeature => "PHONE_FEATURE",
+    PlaybackStart => "PLAYBACK_START",
+    PlaybackStop => "PLAYBACK_STOP",
+    PresenceIn => "PRESENCE_IN",
+    PresenceOut => "PRESENCE_OUT",
+    PresenceProbe => "PRESENCE_PROBE",
+    PrivateCommand => "PRIVATE_COMMAND",
+    Publish => "PUBLISH",
+    RecordStart => "RECORD_START",
+    RecordStop => "RECORD_STOP",
+    RecvInfo => "RECV_INFO",
+    RecvMessage => "RECV_MESSAGE",
+    RecvRtcpMessage => "RECV_RTCP_MESSAGE",
+    ReloadXml => "RELOADXML",
+    ReSchedule => "RE_SCHEDULE",
+    RequestParams => "REQUEST_PARAMS",
+    Roster => "ROSTER",
+    SendInfo => "SEND_INFO",
+    SendMessage => "SEND_MESSAGE",
+    SessionHeartbeat => "SESSION_HEARTBEAT",
+    Shutdown => "SHUTDOWN",
+    Startup => "STARTUP",
+    Talk => "TALK",
+    Trap => "TRAP",
+    Unpublish => "UNPUBLISH",
+}
+
+impl Display for EventName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Returned when parsing a string that isn't a known [`EventName`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownEventName(pub String);
+
+impl Display for UnknownEventName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown event name: {}", self.0)
+    }
+}
+impl std::error::Error for UnknownEventName {}
+
+/// Encoding FreeSWITCH uses for events sent over the connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SubscriptionFormat {
+    #[default]
+    Plain,
+    Json,
+}
+
+/// A set of events to receive, built up and sent with
+/// [`ESLConnection::subscribe`](crate::ESLConnection::subscribe).
+///
+/// # Examples
+///
+/// ```
+/// use eslrs::{Command, EventName::*, EventSubscription, SubscriptionFormat::Json};
+///
+/// let sub = EventSubscription::new()
+///     .format(Json)
+///     .events([ChannelCreate, ChannelHangup])
+///     .custom(["sofia::register", "conference::maintenance"]);
+///
+/// // event json CHANNEL_CREATE CHANNEL_HANGUP CUSTOM sofia::register conference::maintenance
+/// let cmd: Command = sub.command();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EventSubscription {
+    format: SubscriptionFormat,
+    events: Vec<EventName>,
+    custom: Vec<String>,
+}
+
+impl EventSubscription {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribes to every event, including all `CUSTOM` subclasses.
+    pub fn all() -> Self {
+        Self::new().events([EventName::All])
+    }
+
+    pub fn format(mut self, format: SubscriptionFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Adds event types, duplicates are ignored.
+    pub fn events(mut self, events: impl IntoIterator<Item = EventName>) -> Self {
+        for e in events {
+            if !self.events.contains(&e) {
+                self.events.push(e);
+            }
+        }
+        self
+    }
+
+    /// Adds `CUSTOM` event subclasses, ie. `sofia::register`.
+    pub fn custom<T: Into<String>>(mut self, subclasses: impl IntoIterator<Item = T>) -> Self {
+        for s in subclasses {
+            let s = s.into();
+            if !self.custom.contains(&s) {
+                self.custom.push(s);
+            }
+        }
+        self
+    }
+
+    pub fn get_format(&self) -> SubscriptionFormat {
+        self.format
+    }
+
+    pub fn get_events(&self) -> &[EventName] {
+        &self.events
+    }
+
+    pub fn get_custom(&self) -> &[String] {
+        &self.custom
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty() && self.custom.is_empty()
+    }
+
+    /// The `event` command subscribing to this set.
+    pub fn command(&self) -> Command<'static> {
+        match self.format {
+            SubscriptionFormat::Plain => Command::events(self.args()),
+            SubscriptionFormat::Json => Command::events_json(self.args()),
+        }
+    }
+
+    /// The `nixevent` command unsubscribing from this set.
+    pub fn nix_command(&self) -> Command<'static> {
+        Command::nixevent(self.args())
+    }
+
+    // Merge the effect of a successful `event` command
+    pub(crate) fn add(&mut self, other: &EventSubscription) {
+        self.format = other.format;
+        self.events.retain(|e| !other.events.contains(e));
+        self.events.extend_from_slice(&other.events);
+        self.custom.retain(|s| !other.custom.contains(s));
+        self.custom.extend_from_slice(&other.custom);
+    }
+
+    // Merge the effect of a successful `nixevent` command
+    pub(crate) fn remove(&mut self, other: &EventSubscription) {
+        self.events.retain(|e| !other.events.contains(e));
+        self.custom.retain(|s| !other.custom.contains(s));
+    }
+
+    fn args(&self) -> String {
+        let mut args: Vec<&str> = self.events.iter().map(EventName::as_str).collect();
+        if !self.custom.is_empty() {
+            args.push("CUSTOM");
+            args.extend(self.custom.iter().map(String::as_str));
+        }
+        args.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use EventName::*;
+
+    #[test]
+    fn test_subscription_commands() {
+        let sub = EventSubscription::new()
+            .format(SubscriptionFormat::Json)
+            .events([ChannelCreate, ChannelHangup, ChannelCreate])
+            .custom(["sofia::register", "conference::maintenance"]);
+
+        let cmd = sub.command();
+        assert_eq!(cmd.cmd, "event json ");
+        assert_eq!(
+            cmd.args,
+            "CHANNEL_CREATE CHANNEL_HANGUP CUSTOM sofia::register conference::maintenance"
+        );
+
+        let cmd = EventSubscription::new().events([Heartbeat]).nix_command();
+        assert_eq!(cmd.cmd, "nixevent ");
+        assert_eq!(cmd.args, "HEARTBEAT");
+    }
+
+    #[test]
+    fn test_subscription_tracking() {
+        let mut current = EventSubscription::new().events([ChannelCreate, Heartbeat]);
+        current.add(
+            &EventSubscription::new()
+                .format(SubscriptionFormat::Json)
+                .events([Heartbeat, Dtmf])
+                .custom(["sofia::register"]),
+        );
+        current.remove(&EventSubscription::new().events([ChannelCreate]));
+
+        assert_eq!(current.get_format(), SubscriptionFormat::Json);
+        assert_eq!(current.get_events(), &[Heartbeat, Dtmf]);
+        assert_eq!(current.get_custom(), &["sofia::register".to_string()]);
+    }
+
+    #[test]
+    fn test_event_name_round_trip() {
+        assert_eq!("CHANNEL_HANGUP_COMPLETE".parse(), Ok(ChannelHangupComplete));
+        assert_eq!(ChannelHangupComplete.to_string(), "CHANNEL_HANGUP_COMPLETE");
+        assert!("NOT_AN_EVENT".parse::<EventName>().is_err());
+    }
+}
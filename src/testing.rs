@@ -0,0 +1,847 @@
+//! An in-process FreeSWITCH stand-in for testing ESL clients.
+//!
+//! [`MockFreeSwitch`] speaks the server side of the event socket protocol:
+//! it sends `auth/request`, checks the password, answers `api`, `bgapi`,
+//! `event`, `filter`, `sendmsg` and friends, and delivers synthetic events
+//! emitted through a [`MockHandle`] to subscribed clients. Replies can be
+//! scripted per api command or computed by a callback.
+//!
+//! Filters with a `/pattern/` value are matched as regexes when the `regex`
+//! feature is enabled, and refused with `-ERR` otherwise.
+//!
+//! Enable the `testing` feature, usually only as a dev-dependency.
+//!
+//! # Examples
+//!
+//! ```
+//! use eslrs::testing::{MockEvent, MockFreeSwitch};
+//! use eslrs::{Command, EventMatcher, EventName, EventSubscription, Inbound};
+//! use std::time::Duration;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let mock = MockFreeSwitch::new()
+//!     .api("status", "UP 0 years, 0 days\n")
+//!     .listen()
+//!     .await
+//!     .unwrap();
+//!
+//! let mut conn = Inbound::connect(mock.addr().unwrap(), "ClueCon").await.unwrap();
+//! let reply = conn.send_recv(Command::api("status")).await.unwrap();
+//! assert!(reply.is_ok());
+//!
+//! conn.subscribe(&EventSubscription::all()).await.unwrap();
+//! mock.emit(MockEvent::new(EventName::ChannelAnswer).header("Unique-ID", "abc"));
+//!
+//! let answered = EventMatcher::new().event_name(EventName::ChannelAnswer);
+//! conn.wait_for(&answered, Duration::from_secs(1)).await.unwrap();
+//! # }
+//! ```
+use crate::{event::percent_encode, matcher::Condition};
+use bytes::Bytes;
+use std::{
+    collections::HashMap,
+    fmt::{Display, Write},
+    io,
+    net::SocketAddr,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{broadcast, mpsc},
+};
+
+type Handler = dyn Fn(&MockCommand) -> Option<MockReply> + Send + Sync;
+
+/// Builder for a mock event socket server.
+#[derive(Clone)]
+pub struct MockFreeSwitch {
+    password: String,
+    api: HashMap<String, String>,
+    handler: Option<Arc<Handler>>,
+    channel: Vec<(String, String)>,
+}
+
+impl Default for MockFreeSwitch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockFreeSwitch {
+    /// A server accepting the default `ClueCon` password.
+    pub fn new() -> Self {
+        Self {
+            password: "ClueCon".to_string(),
+            api: HashMap::new(),
+            handler: None,
+            channel: vec![],
+        }
+    }
+
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.password = password.into();
+        self
+    }
+
+    /// Scripts the response to `api <command>` and `bgapi <command>`.
+    ///
+    /// Matched against the full command line first, then its first word.
+    pub fn api(mut self, command: impl Into<String>, response: impl Into<String>) -> Self {
+        self.api.insert(command.into(), response.into());
+        self
+    }
+
+    /// Computes replies with `handler`, falling back to the default
+    /// behaviour when it returns `None`.
+    pub fn on_command<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&MockCommand) -> Option<MockReply> + Send + Sync + 'static,
+    {
+        self.handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Adds a channel variable to the reply of an outbound `connect`.
+    pub fn channel_data(mut self, header: impl Into<String>, value: impl Into<String>) -> Self {
+        self.channel.push((header.into(), value.into()));
+        self
+    }
+
+    /// Accepts inbound connections on a random local port,
+    /// see [`MockHandle::addr`].
+    pub async fn listen(self) -> io::Result<MockHandle> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let mut handle = MockHandle::new(self);
+        handle.addr = Some(listener.local_addr()?);
+
+        let server = handle.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                server.serve(stream, false);
+            }
+        });
+        Ok(handle)
+    }
+
+    /// Serves a single in-memory connection, pass the returned stream to
+    /// [`Inbound::handshake`](crate::Inbound::handshake).
+    pub fn duplex(self) -> (tokio::io::DuplexStream, MockHandle) {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let handle = MockHandle::new(self);
+        handle.serve(server, false);
+        (client, handle)
+    }
+
+    /// Connects to an [`Outbound`](crate::Outbound) listener at `addr`,
+    /// as FreeSWITCH does when a call hits the `socket` application.
+    pub async fn connect_outbound(self, addr: impl ToSocketAddrs) -> io::Result<MockHandle> {
+        let stream = TcpStream::connect(addr).await?;
+        let handle = MockHandle::new(self);
+        handle.serve(stream, true);
+        Ok(handle)
+    }
+}
+
+/// A command received by [`MockFreeSwitch`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockCommand {
+    /// First word of the command line, ie. `api`
+    pub name: String,
+    /// Rest of the command line, ie. `status`
+    pub args: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Bytes>,
+}
+
+impl MockCommand {
+    /// The body as text, `None` without a body or if it isn't UTF-8.
+    pub fn body_str(&self) -> Option<&str> {
+        self.body.as_ref().and_then(|b| std::str::from_utf8(b).ok())
+    }
+
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// A reply sent by [`MockFreeSwitch`] in answer to a [`MockCommand`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum MockReply {
+    /// `command/reply` with the given `Reply-Text`
+    Command(String),
+    /// `api/response` with the given body
+    Api(String),
+}
+
+/// A synthetic event emitted through [`MockHandle::emit`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockEvent {
+    name: String,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+}
+
+impl MockEvent {
+    pub fn new(name: impl Display) -> Self {
+        Self {
+            name: name.to_string(),
+            headers: vec![],
+            body: None,
+        }
+    }
+
+    /// A `CUSTOM` event of the given subclass.
+    pub fn custom(subclass: impl Into<String>) -> Self {
+        Self::new("CUSTOM").header("Event-Subclass", subclass)
+    }
+
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    fn get_header(&self, name: &str) -> Option<&str> {
+        if name.eq_ignore_ascii_case("Event-Name") {
+            return Some(&self.name);
+        }
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn all_headers(&self, seq: u64) -> Vec<(&str, String)> {
+        let mut headers = vec![
+            ("Event-Name", self.name.clone()),
+            ("Core-UUID", uuid(0)),
+            ("FreeSWITCH-Hostname", "mock".to_string()),
+            ("Event-Sequence", seq.to_string()),
+        ];
+        headers.extend(self.headers.iter().map(|(k, v)| (k.as_str(), v.clone())));
+        headers
+    }
+
+    fn to_plain(&self, seq: u64) -> String {
+        let mut s = String::new();
+        for (k, v) in self.all_headers(seq) {
+            let _ = writeln!(s, "{k}: {}", percent_encode(&v));
+        }
+        match &self.body {
+            Some(body) => {
+                let _ = write!(s, "Content-Length: {}\n\n{body}", body.len());
+            }
+            None => s.push('\n'),
+        }
+        s
+    }
+
+    fn to_json(&self, seq: u64) -> String {
+        let mut fields: Vec<String> = self
+            .all_headers(seq)
+            .iter()
+            .map(|(k, v)| format!("{}:{}", json_string(k), json_string(v)))
+            .collect();
+        if let Some(body) = &self.body {
+            fields.push(format!("\"_body\":{}", json_string(body)));
+        }
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+/// Controls a running [`MockFreeSwitch`], cloning shares the same server.
+#[derive(Clone)]
+pub struct MockHandle {
+    config: Arc<MockFreeSwitch>,
+    addr: Option<SocketAddr>,
+    events: broadcast::Sender<MockEvent>,
+    commands: Arc<Mutex<Vec<MockCommand>>>,
+    sequence: Arc<AtomicU64>,
+}
+
+impl MockHandle {
+    fn new(config: MockFreeSwitch) -> Self {
+        Self {
+            config: Arc::new(config),
+            addr: None,
+            events: broadcast::channel(1024).0,
+            commands: Arc::default(),
+            sequence: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    /// Address accepted connections arrive on, if created with
+    /// [`MockFreeSwitch::listen`].
+    pub fn addr(&self) -> Option<SocketAddr> {
+        self.addr
+    }
+
+    /// Sends `event` to every connection subscribed to it.
+    pub fn emit(&self, event: MockEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Every command received so far, across all connections.
+    pub fn commands(&self) -> Vec<MockCommand> {
+        self.commands.lock().unwrap().clone()
+    }
+
+    fn next_seq(&self) -> u64 {
+        self.sequence.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn serve<S>(&self, stream: S, outbound: bool)
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (read, write) = tokio::io::split(stream);
+        let (tx, rx) = mpsc::channel(16);
+        tokio::spawn(async move {
+            let mut read = BufReader::new(read);
+            while let Ok(Some(cmd)) = read_command(&mut read).await {
+                if tx.send(cmd).await.is_err() {
+                    break;
+                }
+            }
+        });
+        let session = Session {
+            handle: self.clone(),
+            write,
+            authed: outbound,
+            outbound,
+            json: false,
+            events: vec![],
+            custom: vec![],
+            filters: vec![],
+        };
+        tokio::spawn(session.run(rx, self.events.subscribe()));
+    }
+}
+
+struct Session<W> {
+    handle: MockHandle,
+    write: W,
+    authed: bool,
+    outbound: bool,
+    json: bool,
+    events: Vec<String>,
+    custom: Vec<String>,
+    // Along with the value as sent, to match `filter delete`
+    filters: Vec<(Condition, String)>,
+}
+
+impl<W: AsyncWrite + Unpin> Session<W> {
+    async fn run(
+        mut self,
+        mut commands: mpsc::Receiver<MockCommand>,
+        mut events: broadcast::Receiver<MockEvent>,
+    ) -> io::Result<()> {
+        if !self.outbound {
+            self.write_frame(&[("Content-Type", "auth/request")], None)
+                .await?;
+        }
+        loop {
+            tokio::select! {
+                cmd = commands.recv() => match cmd {
+                    Some(cmd) => {
+                        if !self.handle_command(cmd).await? {
+                            return self.write.shutdown().await;
+                        }
+                    }
+                    None => return Ok(()),
+                },
+                event = events.recv() => match event {
+                    Ok(event) => self.send_event(&event).await?,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                },
+            }
+        }
+    }
+
+    // Returns false once the connection should close
+    async fn handle_command(&mut self, cmd: MockCommand) -> io::Result<bool> {
+        self.handle.commands.lock().unwrap().push(cmd.clone());
+        let config = self.handle.config.clone();
+
+        if !self.authed {
+            let password = match cmd.name.as_str() {
+                "auth" => Some(cmd.args.as_str()),
+                "userauth" => cmd.args.split_once(':').map(|(_, p)| p),
+                _ => None,
+            };
+            if password == Some(config.password.as_str()) {
+                self.authed = true;
+                self.reply("+OK accepted").await?;
+                return Ok(true);
+            }
+            self.reply("-ERR invalid").await?;
+            self.disconnect_notice().await?;
+            return Ok(false);
+        }
+
+        if let Some(reply) = config.handler.as_ref().and_then(|h| h(&cmd)) {
+            match reply {
+                MockReply::Command(text) => self.reply(&text).await?,
+                MockReply::Api(body) => self.api_response(&body).await?,
+            }
+            return Ok(true);
+        }
+
+        match cmd.name.as_str() {
+            "api" => {
+                let body = api_result(&config, &cmd.args);
+                self.api_response(&body).await?;
+            }
+            "bgapi" => {
+                let job = match cmd.get_header("Job-UUID") {
+                    Some(job) => job.to_string(),
+                    None => uuid(self.handle.next_seq()),
+                };
+                self.reply(&format!("+OK Job-UUID: {job}")).await?;
+                let body = api_result(&config, &cmd.args);
+                let event = MockEvent::new("BACKGROUND_JOB")
+                    .header("Job-UUID", job)
+                    .header(
+                        "Job-Command",
+                        cmd.args.split(' ').next().unwrap_or_default(),
+                    )
+                    .body(body);
+                self.send_event(&event).await?;
+            }
+            "event" => {
+                let mut words = cmd.args.split_whitespace();
+                self.json = words.next() == Some("json");
+                let mut custom = false;
+                for w in words {
+                    match w {
+                        "CUSTOM" => custom = true,
+                        w if custom => self.custom.push(w.to_string()),
+                        w => self.events.push(w.to_string()),
+                    }
+                }
+                let format = if self.json { "json" } else { "plain" };
+                self.reply(&format!("+OK event listener enabled {format}"))
+                    .await?;
+            }
+            "nixevent" => {
+                for w in cmd.args.split_whitespace() {
+                    self.events.retain(|e| e != w);
+                    self.custom.retain(|e| e != w);
+                }
+                self.reply("+OK events nixed").await?;
+            }
+            "noevents" => {
+                self.events.clear();
+                self.custom.clear();
+                self.reply("+OK no longer listening for events").await?;
+            }
+            "filter" => match cmd.args.split_once(' ') {
+                Some(("delete", "all")) => {
+                    self.filters.clear();
+                    self.reply("+OK filter deleted.").await?;
+                }
+                Some(("delete", rest)) => {
+                    let (header, value) = rest.split_once(' ').unwrap_or((rest, ""));
+                    self.filters
+                        .retain(|(c, v)| c.header() != header || (!value.is_empty() && v != value));
+                    self.reply("+OK filter deleted.").await?;
+                }
+                Some((header, value)) => match filter_condition(header, value) {
+                    Ok(condition) => {
+                        self.filters.push((condition, value.to_string()));
+                        self.reply(&format!("+OK filter added. [{header}]=[{value}]"))
+                            .await?;
+                    }
+                    Err(e) => self.reply(&format!("-ERR {e}")).await?,
+                },
+                None => self.reply("-ERR invalid syntax").await?,
+            },
+            "sendmsg" | "myevents" | "linger" | "nolinger" | "divert_events" | "resume" => {
+                self.reply("+OK").await?
+            }
+            "sendevent" => {
+                let uuid = uuid(self.handle.next_seq());
+                self.reply(&format!("+OK {uuid}")).await?;
+            }
+            "connect" if self.outbound => {
+                let mut headers = vec![
+                    ("Content-Type".to_string(), "command/reply".to_string()),
+                    ("Reply-Text".to_string(), "+OK".to_string()),
+                    ("Event-Name".to_string(), "CHANNEL_DATA".to_string()),
+                ];
+                if !config.channel.iter().any(|(k, _)| k == "Unique-ID") {
+                    headers.push(("Unique-ID".to_string(), uuid(self.handle.next_seq())));
+                }
+                headers.extend(config.channel.iter().cloned());
+                let headers: Vec<_> = headers
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v.as_str()))
+                    .collect();
+                self.write_frame(&headers, None).await?;
+            }
+            "exit" => {
+                self.reply("+OK bye").await?;
+                self.disconnect_notice().await?;
+                return Ok(false);
+            }
+            _ => self.reply("-ERR command not found").await?,
+        }
+        Ok(true)
+    }
+
+    fn subscribed(&self, event: &MockEvent) -> bool {
+        let name = event.name.as_str();
+        let listening = self.events.iter().any(|e| e == "ALL" || e == name)
+            || (name == "CUSTOM"
+                && event
+                    .get_header("Event-Subclass")
+                    .is_some_and(|s| self.custom.iter().any(|c| c == s)));
+        let filtered = self.filters.is_empty()
+            || self
+                .filters
+                .iter()
+                .any(|(c, _)| event.get_header(c.header()).is_some_and(|v| c.is_match(v)));
+        listening && filtered
+    }
+
+    async fn send_event(&mut self, event: &MockEvent) -> io::Result<()> {
+        if !self.authed || !self.subscribed(event) {
+            return Ok(());
+        }
+        let seq = self.handle.next_seq();
+        let (content_type, body) = match self.json {
+            true => ("text/event-json", event.to_json(seq)),
+            false => ("text/event-plain", event.to_plain(seq)),
+        };
+        self.write_frame(&[("Content-Type", content_type)], Some(&body))
+            .await
+    }
+
+    async fn reply(&mut self, text: &str) -> io::Result<()> {
+        self.write_frame(
+            &[("Content-Type", "command/reply"), ("Reply-Text", text)],
+            None,
+        )
+        .await
+    }
+
+    async fn api_response(&mut self, body: &str) -> io::Result<()> {
+        self.write_frame(&[("Content-Type", "api/response")], Some(body))
+            .await
+    }
+
+    async fn disconnect_notice(&mut self) -> io::Result<()> {
+        self.write_frame(
+            &[("Content-Type", "text/disconnect-notice")],
+            Some("Disconnected, goodbye.\nSee you at ClueCon! http://www.cluecon.com/\n"),
+        )
+        .await
+    }
+
+    async fn write_frame(
+        &mut self,
+        headers: &[(&str, &str)],
+        body: Option<&str>,
+    ) -> io::Result<()> {
+        let mut frame = String::new();
+        for (k, v) in headers {
+            let _ = writeln!(frame, "{k}: {v}");
+        }
+        if let Some(body) = body {
+            let _ = writeln!(frame, "Content-Length: {}", body.len());
+        }
+        frame.push('\n');
+        frame.push_str(body.unwrap_or_default());
+        self.write.write_all(frame.as_bytes()).await?;
+        self.write.flush().await
+    }
+}
+
+fn api_result(config: &MockFreeSwitch, args: &str) -> String {
+    let name = args.split(' ').next().unwrap_or_default();
+    config
+        .api
+        .get(args)
+        .or_else(|| config.api.get(name))
+        .cloned()
+        .unwrap_or_else(|| format!("-ERR {name} Command not found!\n"))
+}
+
+// `/pattern/` values are regexes, as in mod_event_socket
+fn filter_condition(header: &str, value: &str) -> Result<Condition, String> {
+    let Some(pattern) = value
+        .strip_prefix('/')
+        .and_then(|v| v.strip_suffix('/'))
+        .filter(|_| value.len() > 1)
+    else {
+        return Ok(Condition::Equals(header.to_string(), value.to_string()));
+    };
+    #[cfg(feature = "regex")]
+    {
+        regex::Regex::new(pattern)
+            .map(|re| Condition::Regex(header.to_string(), re))
+            .map_err(|_| format!("invalid regex {pattern}"))
+    }
+    #[cfg(not(feature = "regex"))]
+    {
+        Err(format!(
+            "regex filter /{pattern}/ requires the regex feature"
+        ))
+    }
+}
+
+async fn read_command<R>(read: &mut R) -> io::Result<Option<MockCommand>>
+where
+    R: AsyncBufReadExt + Unpin,
+{
+    let mut line = String::new();
+    // Skip blank lines left over from the previous command's terminator
+    let command = loop {
+        line.clear();
+        if read.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let trimmed = line.trim();
+        if !trimmed.is_empty() {
+            break trimmed.to_string();
+        }
+    };
+
+    let mut headers = vec![];
+    loop {
+        line.clear();
+        if read.read_line(&mut line).await? == 0 {
+            break;
+        }
+        let trimmed = line.trim_end_matches(['\r', '\n']);
+        if trimmed.is_empty() {
+            break;
+        }
+        if let Some((k, v)) = trimmed.split_once(':') {
+            headers.push((k.trim().to_string(), v.trim().to_string()));
+        }
+    }
+
+    let len = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("Content-Length"))
+        .and_then(|(_, v)| v.parse::<usize>().ok());
+    let body = match len {
+        Some(len) => {
+            let mut body = vec![0; len];
+            read.read_exact(&mut body).await?;
+            Some(Bytes::from(body))
+        }
+        None => None,
+    };
+
+    let (name, args) = command.split_once(' ').unwrap_or((&command, ""));
+    Ok(Some(MockCommand {
+        name: name.to_string(),
+        args: args.to_string(),
+        headers,
+        body,
+    }))
+}
+
+fn uuid(n: u64) -> String {
+    format!("00000000-0000-4000-8000-{n:012x}")
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Command, ConnectError, ESLConfig, EventMatcher, EventName, EventSubscription, Filter,
+        Inbound, Outbound,
+    };
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_mock_inbound_api_and_events() {
+        let mock = MockFreeSwitch::new()
+            .api("status", "UP 0 years\n")
+            .listen()
+            .await
+            .unwrap();
+        let mut conn = Inbound::connect(mock.addr().unwrap(), "ClueCon")
+            .await
+            .unwrap();
+
+        let reply = conn.send_recv(Command::api("status")).await.unwrap();
+        assert_eq!(reply.get_body().unwrap().as_ref(), b"UP 0 years\n");
+
+        let sub = EventSubscription::new().events([EventName::ChannelAnswer]);
+        conn.subscribe(&sub).await.unwrap();
+        conn.add_filter(&Filter::unique_id("abc")).await.unwrap();
+
+        // Not subscribed, filtered out, then delivered
+        mock.emit(MockEvent::new(EventName::Heartbeat));
+        mock.emit(MockEvent::new(EventName::ChannelAnswer).header("Unique-ID", "other"));
+        mock.emit(MockEvent::new(EventName::ChannelAnswer).header("Unique-ID", "abc"));
+
+        let answered = EventMatcher::new().unique_id("abc");
+        conn.wait_for(&answered, Duration::from_secs(1))
+            .await
+            .unwrap();
+        let names: Vec<_> = mock.commands().into_iter().map(|c| c.name).collect();
+        assert_eq!(names, ["auth", "api", "event", "filter"]);
+    }
+
+    #[tokio::test]
+    async fn test_mock_regex_filter() {
+        let (stream, mock) = MockFreeSwitch::new().duplex();
+        let mut conn = Inbound::handshake(stream, "ClueCon").await.unwrap();
+        conn.subscribe(&EventSubscription::new().events([EventName::ChannelCreate]))
+            .await
+            .unwrap();
+        let filter = Filter::regex("Caller-Destination-Number", "^9[0-9]{3}$");
+        let reply = conn.add_filter(&filter).await.unwrap();
+
+        #[cfg(feature = "regex")]
+        {
+            assert!(reply.is_ok());
+            let create = |n: &str| {
+                MockEvent::new(EventName::ChannelCreate).header("Caller-Destination-Number", n)
+            };
+            mock.emit(create("1000"));
+            mock.emit(create("9100"));
+            let event = conn.recv().await.unwrap();
+            let event = crate::event::PlainEvent::try_from(event).unwrap();
+            assert_eq!(event.get_header("Caller-Destination-Number"), Some("9100"));
+        }
+        #[cfg(not(feature = "regex"))]
+        {
+            let _ = mock;
+            assert!(!reply.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_bgapi_interleaves_reply_and_event() {
+        let (stream, _mock) = MockFreeSwitch::new()
+            .api("version", "FreeSWITCH 1.10\n")
+            .duplex();
+        let mut conn = Inbound::handshake(stream, "ClueCon").await.unwrap();
+        conn.subscribe(&EventSubscription::new().events([EventName::BackgroundJob]))
+            .await
+            .unwrap();
+
+        let reply = conn
+            .send_recv(Command::bgapi("version", "job-1"))
+            .await
+            .unwrap();
+        assert_eq!(reply.get_header("Reply-Text"), Some("+OK Job-UUID: job-1"));
+
+        let job = EventMatcher::new().header("Job-UUID", "job-1");
+        let event = conn.wait_for(&job, Duration::from_secs(1)).await.unwrap();
+        let event = crate::event::PlainEvent::try_from(event).unwrap();
+        assert_eq!(event.get_body().unwrap().as_ref(), b"FreeSWITCH 1.10\n");
+    }
+
+    #[tokio::test]
+    async fn test_mock_rejects_bad_password() {
+        let (stream, _mock) = MockFreeSwitch::new().password("secret").duplex();
+        let res = Inbound::handshake(stream, "ClueCon").await;
+        assert!(matches!(res, Err(ConnectError::Auth(text)) if text == "-ERR invalid"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_callback_replies() {
+        let (stream, mock) = MockFreeSwitch::new()
+            .on_command(|cmd| {
+                (cmd.name == "sendmsg").then(|| MockReply::Command("-ERR no such channel".into()))
+            })
+            .duplex();
+        let mut conn = Inbound::handshake(stream, "ClueCon").await.unwrap();
+
+        let reply = conn
+            .send_recv(Command::execute(
+                "abc",
+                "playback",
+                "tone_stream://%(100,0,440)",
+            ))
+            .await
+            .unwrap();
+        assert!(!reply.is_ok());
+        let sendmsg = mock.commands().pop().unwrap();
+        assert_eq!(sendmsg.args, "abc");
+        assert_eq!(sendmsg.get_header("execute-app-name"), Some("playback"));
+        assert_eq!(sendmsg.body_str(), Some("tone_stream://%(100,0,440)"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_records_binary_body() {
+        let (stream, mock) = MockFreeSwitch::new().duplex();
+        let mut conn = Inbound::handshake(stream, "ClueCon").await.unwrap();
+
+        let event = crate::event::OutgoingEvent::custom("myapp::blob")
+            .body(&b"\x00\xff\n"[..], "application/octet-stream");
+        let reply = conn.send_recv(event.command().unwrap()).await.unwrap();
+        assert!(reply.is_ok());
+
+        let sendevent = mock.commands().pop().unwrap();
+        assert_eq!(sendevent.name, "sendevent");
+        assert_eq!(sendevent.body.as_deref(), Some(&b"\x00\xff\n"[..]));
+        assert_eq!(sendevent.body_str(), None);
+    }
+
+    #[tokio::test]
+    async fn test_mock_connects_outbound() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mock = MockFreeSwitch::new()
+            .channel_data("Unique-ID", "call-1")
+            .channel_data("Caller-Destination-Number", "9196")
+            .connect_outbound(addr)
+            .await
+            .unwrap();
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut conn = Outbound::handshake(stream, ESLConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(conn.get_info().get_header("Unique-ID"), Some("call-1"));
+        assert_eq!(
+            conn.get_info().get_header("Caller-Destination-Number"),
+            Some("9196")
+        );
+
+        let report = conn.shutdown(Default::default()).await;
+        assert!(report.result.is_ok());
+        assert_eq!(mock.commands().last().unwrap().name, "exit");
+    }
+}
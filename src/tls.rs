@@ -0,0 +1,350 @@
+//! TLS transport for inbound and outbound connections.
+//!
+//! ESL itself is plaintext, so FreeSWITCH is usually fronted by a TLS
+//! terminating proxy such as stunnel. Enable the `tls` feature to connect
+//! to (or accept connections from) such a proxy using rustls.
+//!
+//! Client certificate verification for the outbound server is configured on
+//! the [`rustls::ServerConfig`] given to [`Outbound::handshake_tls`]:
+//!
+//! ```no_run
+//! use eslrs::tls::rustls::{RootCertStore, ServerConfig, server::WebPkiClientVerifier};
+//! use eslrs::tls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+//! use std::sync::Arc;
+//!
+//! # fn example(
+//! #     client_ca: CertificateDer<'static>,
+//! #     cert_chain: Vec<CertificateDer<'static>>,
+//! #     key: PrivateKeyDer<'static>,
+//! # ) {
+//! let mut roots = RootCertStore::empty();
+//! roots.add(client_ca).unwrap();
+//! let verifier = WebPkiClientVerifier::builder(Arc::new(roots)).build().unwrap();
+//! let tls_config = ServerConfig::builder()
+//!     .with_client_cert_verifier(verifier)
+//!     .with_single_cert(cert_chain, key)
+//!     .unwrap();
+//! # }
+//! ```
+use std::{io, sync::Arc};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpStream, ToSocketAddrs},
+};
+use tokio_rustls::{
+    TlsAcceptor,
+    rustls::{ClientConfig, ServerConfig, pki_types::CertificateDer, pki_types::ServerName},
+};
+
+use crate::{
+    ConnectError, Connector, ESLConfig, Inbound, Outbound, TcpConnector,
+    rt::{Driver, Tokio},
+};
+
+pub use tokio_rustls::client::TlsStream as ClientTlsStream;
+pub use tokio_rustls::rustls;
+pub use tokio_rustls::server::TlsStream as ServerTlsStream;
+
+/// Wraps the stream of another [`Connector`] in a TLS session.
+///
+/// # Examples
+///
+/// ```no_run
+/// use eslrs::{Inbound, TcpConnector};
+/// use eslrs::tls::{TlsConnector, rustls::ClientConfig};
+/// use std::sync::Arc;
+///
+/// # async fn example(tls_config: Arc<ClientConfig>) {
+/// let tcp = TcpConnector::new("fs1.example.com:8022");
+/// let connector = TlsConnector::new(tcp, "fs1.example.com", tls_config);
+/// let mut conn = Inbound::connect_with(connector, "ClueCon").await.unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct TlsConnector<C> {
+    inner: C,
+    server_name: String,
+    tls_config: Arc<ClientConfig>,
+}
+
+impl<C> TlsConnector<C> {
+    pub fn new<N: Into<String>>(inner: C, server_name: N, tls_config: Arc<ClientConfig>) -> Self {
+        Self {
+            inner,
+            server_name: server_name.into(),
+            tls_config,
+        }
+    }
+}
+
+impl<C> Connector for TlsConnector<C>
+where
+    C: Connector,
+    C::Stream: AsyncRead + AsyncWrite,
+{
+    type Stream = ClientTlsStream<C::Stream>;
+
+    async fn connect(self) -> io::Result<Self::Stream> {
+        let server_name = ServerName::try_from(self.server_name)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let stream = self.inner.connect().await?;
+        tokio_rustls::TlsConnector::from(self.tls_config)
+            .connect(server_name, stream)
+            .await
+    }
+}
+
+impl Inbound<ClientTlsStream<TcpStream>> {
+    /// Connects to a FreeSWITCH Event Socket over TLS and authenticates.
+    ///
+    /// Same as [`Inbound::connect`], but the TCP stream is wrapped in a TLS session
+    /// verified against `server_name` before the ESL handshake begins.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use eslrs::Inbound;
+    /// use eslrs::tls::rustls::{ClientConfig, RootCertStore};
+    /// use std::sync::Arc;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// # let roots = RootCertStore::empty();
+    /// let tls_config = ClientConfig::builder()
+    ///     .with_root_certificates(roots)
+    ///     .with_no_client_auth();
+    /// let mut conn = Inbound::connect_tls("fs1.example.com:8022", "fs1.example.com", Arc::new(tls_config), "ClueCon")
+    ///     .await
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub async fn connect_tls<U: ToSocketAddrs, V: Into<ESLConfig>>(
+        addr: U,
+        server_name: &str,
+        tls_config: Arc<ClientConfig>,
+        config: V,
+    ) -> Result<Inbound<ClientTlsStream<TcpStream>>, ConnectError> {
+        let config: ESLConfig = config.into();
+        let tcp = TcpConnector::new(addr).keepalive(config.tcp_keepalive);
+        let connector = TlsConnector::new(tcp, server_name, tls_config);
+        Inbound::connect_with(connector, config).await
+    }
+}
+
+impl<T> Outbound<ServerTlsStream<T>>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Terminates TLS on an accepted stream and performs the outbound socket handshake.
+    ///
+    /// If `tls_config` has a client certificate verifier, connections without
+    /// a valid client certificate are refused during the TLS handshake.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use eslrs::{Outbound, ESLConfig};
+    /// use eslrs::tls::rustls::ServerConfig;
+    /// use std::sync::Arc;
+    /// use tokio::net::TcpListener;
+    ///
+    /// # async fn example(tls_config: ServerConfig) {
+    /// let tls_config = Arc::new(tls_config);
+    /// let listener = TcpListener::bind("0.0.0.0:8889").await.unwrap();
+    ///
+    /// loop {
+    ///     let (socket, _) = listener.accept().await.unwrap();
+    ///     let tls_config = tls_config.clone();
+    ///     tokio::spawn(async move {
+    ///         let config = ESLConfig::default();
+    ///         let mut conn = Outbound::handshake_tls(socket, tls_config, config).await.unwrap();
+    ///         // Control the call...
+    ///     });
+    /// }
+    /// # }
+    /// ```
+    pub async fn handshake_tls<V: Into<ESLConfig>>(
+        stream: T,
+        tls_config: Arc<ServerConfig>,
+        config: V,
+    ) -> Result<Outbound<ServerTlsStream<T>>, ConnectError> {
+        let config: ESLConfig = config.into();
+        let stream = Driver::<ServerTlsStream<T>>::new::<Tokio>()
+            .timeout(config.timeout, TlsAcceptor::from(tls_config).accept(stream))
+            .await
+            .ok_or(ConnectError::Timeout)??;
+        Outbound::handshake(stream, config).await
+    }
+
+    /// Returns the certificate chain presented by the peer, if client
+    /// certificates were requested.
+    pub fn peer_certificates(&self) -> Option<&[CertificateDer<'static>]> {
+        self.get_ref().get_ref().1.peer_certificates()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{
+        BasicConstraints, CertificateParams, CertifiedKey, IsCa, Issuer, KeyPair,
+        generate_simple_self_signed,
+    };
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+    use tokio_rustls::rustls::{
+        RootCertStore,
+        pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer},
+        server::WebPkiClientVerifier,
+    };
+
+    fn key_der(key: &KeyPair) -> PrivateKeyDer<'static> {
+        PrivatePkcs8KeyDer::from(key.serialize_der()).into()
+    }
+
+    fn server_config(server: &CertifiedKey<KeyPair>) -> ServerConfig {
+        ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![server.cert.der().clone()],
+                key_der(&server.signing_key),
+            )
+            .unwrap()
+    }
+
+    fn client_config(server: &CertifiedKey<KeyPair>) -> ClientConfig {
+        let mut roots = RootCertStore::empty();
+        roots.add(server.cert.der().clone()).unwrap();
+        ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth()
+    }
+
+    #[tokio::test]
+    async fn test_inbound_connect_tls() {
+        let server = generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(server_config(&server)));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let handle = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut socket = BufReader::new(acceptor.accept(socket).await.unwrap());
+            socket
+                .write_all(b"Content-Type: auth/request\n\n")
+                .await
+                .unwrap();
+            let mut line = String::new();
+            socket.read_line(&mut line).await.unwrap();
+            socket
+                .write_all(b"Content-Type: command/reply\nReply-Text: +OK accepted\n\n")
+                .await
+                .unwrap();
+            line.trim().to_string()
+        });
+
+        let tls_config = Arc::new(client_config(&server));
+        let conn = Inbound::connect_tls(addr, "localhost", tls_config, "ClueCon").await;
+        assert!(conn.is_ok(), "{:?}", conn.err());
+        assert_eq!(handle.await.unwrap(), "auth ClueCon");
+    }
+
+    #[tokio::test]
+    async fn test_outbound_handshake_tls_client_auth() {
+        let server = generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+
+        let mut ca_params = CertificateParams::new(vec![]).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca_key = KeyPair::generate().unwrap();
+        let ca_cert = ca_params.self_signed(&ca_key).unwrap();
+        let ca = Issuer::new(ca_params, ca_key);
+        let client_key = KeyPair::generate().unwrap();
+        let client_cert = CertificateParams::new(vec!["freeswitch".to_string()])
+            .unwrap()
+            .signed_by(&client_key, &ca)
+            .unwrap();
+
+        let mut client_roots = RootCertStore::empty();
+        client_roots.add(ca_cert.der().clone()).unwrap();
+        let verifier = WebPkiClientVerifier::builder(Arc::new(client_roots))
+            .build()
+            .unwrap();
+        let tls_config = ServerConfig::builder()
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(
+                vec![server.cert.der().clone()],
+                key_der(&server.signing_key),
+            )
+            .unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // Stand in for FreeSWITCH connecting to our outbound socket
+        let mut roots = RootCertStore::empty();
+        roots.add(server.cert.der().clone()).unwrap();
+        let fs_config = ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_client_auth_cert(vec![client_cert.der().clone()], key_der(&client_key))
+            .unwrap();
+        tokio::spawn(async move {
+            let socket = TcpStream::connect(addr).await.unwrap();
+            let name = ServerName::try_from("localhost").unwrap();
+            let socket = tokio_rustls::TlsConnector::from(Arc::new(fs_config))
+                .connect(name, socket)
+                .await
+                .unwrap();
+            let mut socket = BufReader::new(socket);
+            let mut line = String::new();
+            socket.read_line(&mut line).await.unwrap();
+            socket
+                .write_all(b"Content-Type: command/reply\nReply-Text: +OK\nUnique-ID: abc123\n\n")
+                .await
+                .unwrap();
+            // hold the connection open until the handshake completes
+            socket.read_line(&mut line).await.ok();
+        });
+
+        let (socket, _) = listener.accept().await.unwrap();
+        let conn = Outbound::handshake_tls(socket, Arc::new(tls_config), ESLConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(conn.get_info().get_header("Unique-ID"), Some("abc123"));
+        assert!(conn.peer_certificates().is_some_and(|c| !c.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn test_outbound_handshake_tls_missing_client_cert() {
+        let server = generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let ca = generate_simple_self_signed(vec![]).unwrap();
+        let mut client_roots = RootCertStore::empty();
+        client_roots.add(ca.cert.der().clone()).unwrap();
+        let verifier = WebPkiClientVerifier::builder(Arc::new(client_roots))
+            .build()
+            .unwrap();
+        let tls_config = ServerConfig::builder()
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(
+                vec![server.cert.der().clone()],
+                key_der(&server.signing_key),
+            )
+            .unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let fs_config = Arc::new(client_config(&server));
+        tokio::spawn(async move {
+            let socket = TcpStream::connect(addr).await.unwrap();
+            let name = ServerName::try_from("localhost").unwrap();
+            let _ = tokio_rustls::TlsConnector::from(fs_config)
+                .connect(name, socket)
+                .await;
+        });
+
+        let (socket, _) = listener.accept().await.unwrap();
+        let res = Outbound::handshake_tls(socket, Arc::new(tls_config), ESLConfig::default()).await;
+        assert!(matches!(res, Err(ConnectError::Connection(_))));
+    }
+}
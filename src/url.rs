@@ -0,0 +1,462 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
+#[cfg(feature = "tokio")]
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+#[cfg(feature = "tokio")]
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::TcpStream,
+};
+
+#[cfg(feature = "tokio")]
+use crate::{Connector, TcpConnector};
+use crate::{ESLConfig, EventSubscription, SubscriptionFormat};
+
+const DEFAULT_PORT: u16 = 8021;
+
+/// Where an [`ESLUrl`] points to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Endpoint {
+    /// `esl://host:port`
+    Tcp { host: String, port: u16 },
+    /// `esls://host:port`, requires the `tls` feature to connect and
+    /// trusts the webpki roots only
+    Tls { host: String, port: u16 },
+    /// `unix:///path/to/socket`
+    Unix(PathBuf),
+}
+
+/// Connection details parsed from a URL.
+///
+/// Supported forms are:
+///
+/// * `esl://:password@host:port?timeout=5s`
+/// * `esl://user@domain:password@host:port` for `userauth`
+/// * `esls://:password@host:port` for TLS
+/// * `unix:///path/to/socket?password=secret`
+///
+/// with the optional query parameters:
+///
+/// * `timeout` - connect timeout, ie. `500ms`, `10s` or `1m`
+/// * `events` - events to subscribe to after auth, as `[plain|json:]EVENT,EVENT`
+/// * `user` / `password` - alternative to credentials in the authority
+///
+/// Credentials may be percent-encoded.
+///
+/// Connecting to an `esls://` url verifies the server against the
+/// [webpki roots](https://docs.rs/webpki-roots) only, a server using a
+/// private CA or a self-signed certificate is rejected. To use your own
+/// `ClientConfig`, connect with a `tls::TlsConnector` built from the url's
+/// host and port instead.
+///
+/// # Examples
+///
+/// ```
+/// use eslrs::{ESLUrl, Endpoint};
+///
+/// let url: ESLUrl = "esl://1000@default:secret@fs1:8021?timeout=10s&events=json:CHANNEL_CREATE,CHANNEL_HANGUP"
+///     .parse()
+///     .unwrap();
+///
+/// assert_eq!(url.endpoint, Endpoint::Tcp { host: "fs1".to_string(), port: 8021 });
+/// assert_eq!(url.config.user.as_deref(), Some("1000@default"));
+/// assert_eq!(url.config.password, "secret");
+/// ```
+#[derive(Debug, Clone)]
+pub struct ESLUrl {
+    pub endpoint: Endpoint,
+    pub config: ESLConfig,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UrlError {
+    /// Scheme is not one of `esl`, `esls` or `unix`
+    UnsupportedScheme(String),
+    MissingHost,
+    InvalidPort(String),
+    InvalidTimeout(String),
+    InvalidEvents(String),
+    UnknownParameter(String),
+    /// Malformed percent-encoding
+    InvalidEncoding(String),
+}
+
+impl Error for UrlError {}
+impl Display for UrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UrlError::UnsupportedScheme(s) => write!(f, "unsupported url scheme: {s}"),
+            UrlError::MissingHost => write!(f, "url is missing a host"),
+            UrlError::InvalidPort(s) => write!(f, "invalid port: {s}"),
+            UrlError::InvalidTimeout(s) => write!(f, "invalid timeout: {s}"),
+            UrlError::InvalidEvents(s) => write!(f, "invalid events: {s}"),
+            UrlError::UnknownParameter(s) => write!(f, "unknown url parameter: {s}"),
+            UrlError::InvalidEncoding(s) => write!(f, "invalid percent-encoding: {s}"),
+        }
+    }
+}
+
+impl FromStr for ESLUrl {
+    type Err = UrlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (scheme, rest) = s
+            .split_once("://")
+            .ok_or_else(|| UrlError::UnsupportedScheme(s.to_string()))?;
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query)),
+            None => (rest, None),
+        };
+
+        let mut config = ESLConfig::default();
+        let endpoint = match scheme {
+            "esl" | "esls" => {
+                let authority = rest.trim_end_matches('/');
+                let hostport = match authority.rsplit_once('@') {
+                    Some((userinfo, hostport)) => {
+                        parse_userinfo(userinfo, &mut config)?;
+                        hostport
+                    }
+                    None => authority,
+                };
+                let (host, port) = parse_hostport(hostport)?;
+                if scheme == "esl" {
+                    Endpoint::Tcp { host, port }
+                } else {
+                    Endpoint::Tls { host, port }
+                }
+            }
+            "unix" => {
+                if rest.is_empty() {
+                    return Err(UrlError::MissingHost);
+                }
+                Endpoint::Unix(PathBuf::from(decode(rest)?))
+            }
+            other => return Err(UrlError::UnsupportedScheme(other.to_string())),
+        };
+
+        for param in query.into_iter().flat_map(|q| q.split('&')) {
+            if param.is_empty() {
+                continue;
+            }
+            let (k, v) = param.split_once('=').unwrap_or((param, ""));
+            let v = decode(v)?;
+            match k {
+                "timeout" => config.timeout = parse_duration(&v)?,
+                "events" => config.events = Some(parse_events(&v)?),
+                // Empty like an empty user in the authority, plain `auth`
+                "user" => config.user = Some(v).filter(|u| !u.is_empty()),
+                "password" => config.password = v,
+                other => return Err(UrlError::UnknownParameter(other.to_string())),
+            }
+        }
+
+        Ok(ESLUrl { endpoint, config })
+    }
+}
+
+fn parse_userinfo(userinfo: &str, config: &mut ESLConfig) -> Result<(), UrlError> {
+    let (user, password) = userinfo.split_once(':').unwrap_or((userinfo, ""));
+    let user = decode(user)?;
+    if !user.is_empty() {
+        config.user = Some(user);
+    }
+    config.password = decode(password)?;
+    Ok(())
+}
+
+fn parse_hostport(s: &str) -> Result<(String, u16), UrlError> {
+    // Bracketed ipv6, ie. [::1]:8021
+    let (host, port) = if let Some(rest) = s.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']').ok_or(UrlError::MissingHost)?;
+        (host, rest.strip_prefix(':'))
+    } else {
+        match s.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (s, None),
+        }
+    };
+    if host.is_empty() {
+        return Err(UrlError::MissingHost);
+    }
+    let port = match port {
+        Some(p) => p
+            .parse::<u16>()
+            .map_err(|_| UrlError::InvalidPort(p.to_string()))?,
+        None => DEFAULT_PORT,
+    };
+    Ok((host.to_string(), port))
+}
+
+fn parse_duration(s: &str) -> Result<Duration, UrlError> {
+    let err = || UrlError::InvalidTimeout(s.to_string());
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (n, unit) = s.split_at(split);
+    let n: u64 = n.parse().map_err(|_| err())?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(n)),
+        "" | "s" => Ok(Duration::from_secs(n)),
+        "m" => Ok(Duration::from_secs(n.checked_mul(60).ok_or_else(err)?)),
+        _ => Err(err()),
+    }
+}
+
+fn parse_events(s: &str) -> Result<EventSubscription, UrlError> {
+    let err = || UrlError::InvalidEvents(s.to_string());
+    // CUSTOM subclasses contain ':' too, ie. CUSTOM,sofia::register
+    let (format, events) = match s.split_once(':') {
+        Some((format, events)) if !format.contains(',') && !events.starts_with(':') => {
+            (format, events)
+        }
+        _ => ("plain", s),
+    };
+    let format = match format {
+        "plain" => SubscriptionFormat::Plain,
+        "json" => SubscriptionFormat::Json,
+        _ => return Err(err()),
+    };
+
+    let mut names = events.split(',').filter(|e| !e.is_empty());
+    let mut sub = EventSubscription::new().format(format);
+    for name in names.by_ref() {
+        if name == "CUSTOM" {
+            break;
+        }
+        sub = sub.events([name.parse().map_err(|_| err())?]);
+    }
+    sub = sub.custom(names);
+    if sub.is_empty() {
+        return Err(err());
+    }
+    Ok(sub)
+}
+
+fn decode(s: &str) -> Result<String, UrlError> {
+    let err = || UrlError::InvalidEncoding(s.to_string());
+    let mut out = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        if b == b'%' {
+            let hex = [bytes.next().ok_or_else(err)?, bytes.next().ok_or_else(err)?];
+            let hex = std::str::from_utf8(&hex).map_err(|_| err())?;
+            out.push(u8::from_str_radix(hex, 16).map_err(|_| err())?);
+        } else {
+            out.push(b);
+        }
+    }
+    String::from_utf8(out).map_err(|_| err())
+}
+
+/// Stream produced by connecting to an [`ESLUrl`].
+#[cfg(feature = "tokio")]
+pub enum EndpointStream {
+    Tcp(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<crate::tls::ClientTlsStream<TcpStream>>),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixStream),
+}
+
+#[cfg(feature = "tokio")]
+macro_rules! delegate_stream {
+    ($self:ident, $s:ident => $e:expr) => {
+        match $self.get_mut() {
+            EndpointStream::Tcp($s) => $e,
+            #[cfg(feature = "tls")]
+            EndpointStream::Tls($s) => $e,
+            #[cfg(unix)]
+            EndpointStream::Unix($s) => $e,
+        }
+    };
+}
+
+#[cfg(feature = "tokio")]
+impl AsyncRead for EndpointStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        delegate_stream!(self, s => Pin::new(s).poll_read(cx, buf))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl AsyncWrite for EndpointStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        delegate_stream!(self, s => Pin::new(s).poll_write(cx, buf))
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        delegate_stream!(self, s => Pin::new(s).poll_flush(cx))
+    }
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        delegate_stream!(self, s => Pin::new(s).poll_shutdown(cx))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Connector for ESLUrl {
+    type Stream = EndpointStream;
+
+    async fn connect(self) -> io::Result<Self::Stream> {
+        let keepalive = self.config.tcp_keepalive;
+        match self.endpoint {
+            Endpoint::Tcp { host, port } => {
+                let stream = TcpConnector::new((host, port))
+                    .keepalive(keepalive)
+                    .connect()
+                    .await?;
+                Ok(EndpointStream::Tcp(stream))
+            }
+            #[cfg(feature = "tls")]
+            Endpoint::Tls { host, port } => {
+                use crate::tls::{TlsConnector, rustls};
+                let roots = rustls::RootCertStore {
+                    roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+                };
+                let tls_config = rustls::ClientConfig::builder()
+                    .with_root_certificates(roots)
+                    .with_no_client_auth();
+                let tcp = TcpConnector::new((host.clone(), port)).keepalive(keepalive);
+                let connector = TlsConnector::new(tcp, host, tls_config.into());
+                Ok(EndpointStream::Tls(Box::new(connector.connect().await?)))
+            }
+            #[cfg(not(feature = "tls"))]
+            Endpoint::Tls { .. } => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "esls:// requires the tls feature",
+            )),
+            #[cfg(unix)]
+            Endpoint::Unix(path) => {
+                let stream = crate::UnixConnector(path).connect().await?;
+                Ok(EndpointStream::Unix(stream))
+            }
+            #[cfg(not(unix))]
+            Endpoint::Unix(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "unix:// is only supported on unix platforms",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_password_only() {
+        let url: ESLUrl = "esl://:ClueCon@127.0.0.1:8021?timeout=5s".parse().unwrap();
+        assert_eq!(
+            url.endpoint,
+            Endpoint::Tcp {
+                host: "127.0.0.1".to_string(),
+                port: 8021
+            }
+        );
+        assert_eq!(url.config.password, "ClueCon");
+        assert_eq!(url.config.user, None);
+        assert_eq!(url.config.timeout, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_parse_userauth_and_events() {
+        let url: ESLUrl = "esl://user@domain:se%40cret@fs1:8021?timeout=10s&events=json:CHANNEL_CREATE,CHANNEL_HANGUP"
+            .parse()
+            .unwrap();
+        assert_eq!(url.config.user.as_deref(), Some("user@domain"));
+        assert_eq!(url.config.password, "se@cret");
+        assert_eq!(url.config.timeout, Duration::from_secs(10));
+        let events = url.config.events.unwrap().command();
+        assert_eq!(events.cmd, "event json ");
+        assert_eq!(events.args, "CHANNEL_CREATE CHANNEL_HANGUP");
+    }
+
+    #[test]
+    fn test_parse_custom_events() {
+        let url: ESLUrl = "esl://fs1?events=CHANNEL_CREATE,CUSTOM,sofia::register"
+            .parse()
+            .unwrap();
+        let events = url.config.events.unwrap().command();
+        assert_eq!(events.cmd, "event plain ");
+        assert_eq!(events.args, "CHANNEL_CREATE CUSTOM sofia::register");
+    }
+
+    #[test]
+    fn test_parse_tls_ipv6_default_port() {
+        let url: ESLUrl = "esls://:pw@[::1]?timeout=500ms".parse().unwrap();
+        assert_eq!(
+            url.endpoint,
+            Endpoint::Tls {
+                host: "::1".to_string(),
+                port: 8021
+            }
+        );
+        assert_eq!(url.config.timeout, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_parse_unix() {
+        let url: ESLUrl = "unix:///run/freeswitch/esl.sock?password=ClueCon"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            url.endpoint,
+            Endpoint::Unix(PathBuf::from("/run/freeswitch/esl.sock"))
+        );
+        assert_eq!(url.config.password, "ClueCon");
+    }
+
+    #[test]
+    fn test_parse_empty_user_param() {
+        let url: ESLUrl = "esl://fs1?user=&password=ClueCon".parse().unwrap();
+        assert_eq!(url.config.user, None);
+        assert_eq!(url.config.password, "ClueCon");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = |s: &str| s.parse::<ESLUrl>().unwrap_err();
+        assert!(matches!(err("http://fs1"), UrlError::UnsupportedScheme(_)));
+        assert!(matches!(err("fs1:8021"), UrlError::UnsupportedScheme(_)));
+        assert_eq!(err("esl://:pw@"), UrlError::MissingHost);
+        assert!(matches!(err("esl://fs1:80x"), UrlError::InvalidPort(_)));
+        assert!(matches!(
+            err("esl://fs1?timeout=5h"),
+            UrlError::InvalidTimeout(_)
+        ));
+        assert!(matches!(
+            err("esl://fs1?timeout=18446744073709551615m"),
+            UrlError::InvalidTimeout(_)
+        ));
+        assert!(matches!(
+            err("esl://fs1?events=yaml:ALL"),
+            UrlError::InvalidEvents(_)
+        ));
+        assert!(matches!(
+            err("esl://fs1?events=CHANNEL_CRATE"),
+            UrlError::InvalidEvents(_)
+        ));
+        assert!(matches!(
+            err("esl://fs1?foo=1"),
+            UrlError::UnknownParameter(_)
+        ));
+        assert!(matches!(
+            err("esl://:%zz@fs1"),
+            UrlError::InvalidEncoding(_)
+        ));
+    }
+}